@@ -1,37 +1,100 @@
 use std::collections::HashSet;
 use std::fmt;
 
+// A source span, wide enough to point at an exact character range rather
+// than just a line: `start_offset`/`end_offset` index into the source text,
+// `line`/`col` are the 1-based position of the first character
 #[derive(Debug, Clone)]
 pub struct Src {
     pub file: String,
     pub line: usize,
+    pub col: usize,
+    pub start_offset: usize,
+    pub end_offset: usize,
 }
 
 impl fmt::Display for Src {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}:{}", self.file, self.line)
+        write!(f, "{}:{}:{}", self.file, self.line, self.col)
     }
 }
 
 #[derive(Debug, Clone)]
 pub enum Expr {
-    Assign(Box<Expr>, Box<Expr>),
-    Atom(String),
-    Call(Box<Expr>, Vec<Expr>),
-    Infix(String, Box<Expr>, Box<Expr>),
-    List(Vec<Expr>),
-    Object(Vec<Expr>),
-    Prefix(String, Box<Expr>),
-    Slice(Box<Expr>, Box<Expr>, Box<Expr>),
-    Subscript(Box<Expr>, Box<Expr>),
-    Typeof(Box<Expr>),
+    Assign(Src, Box<Expr>, Box<Expr>),
+    Atom(Src, String),
+    Call(Src, Box<Expr>, Vec<Expr>),
+    // A placeholder standing in for an expression the parser couldn't make
+    // sense of, inserted by error-recovery mode so the surrounding AST
+    // still builds even though parsing as a whole failed
+    Error(Src),
+    Infix(Src, String, Box<Expr>, Box<Expr>),
+    // `x -> expr`/`(a, b) -> expr`: an inline closure over a single
+    // expression, capturing enclosing variables unless shadowed by a
+    // parameter
+    Lambda(Src, Vec<String>, Box<Expr>),
+    // `(a, b) -> ... end`: the block-bodied form, for closures that need
+    // more than one statement
+    LambdaBlock(Src, Vec<String>, Vec<Stmt>),
+    List(Src, Vec<Expr>),
+    Object(Src, Vec<Expr>),
+    Prefix(Src, String, Box<Expr>),
+    Slice(Src, Box<Expr>, Box<Expr>, Box<Expr>),
+    Subscript(Src, Box<Expr>, Box<Expr>),
+    // A `` `...${...}...` `` template literal, lowered to its alternating
+    // literal-chunk and interpolated-expression pieces, concatenated in order
+    Template(Src, Vec<Expr>),
+    Typeof(Src, Box<Expr>),
 }
 
-#[derive(Debug)]
+impl Expr {
+    pub fn src(&self) -> &Src {
+        match self {
+            Expr::Assign(src, ..)
+            | Expr::Atom(src, ..)
+            | Expr::Call(src, ..)
+            | Expr::Error(src, ..)
+            | Expr::Infix(src, ..)
+            | Expr::Lambda(src, ..)
+            | Expr::LambdaBlock(src, ..)
+            | Expr::List(src, ..)
+            | Expr::Object(src, ..)
+            | Expr::Prefix(src, ..)
+            | Expr::Slice(src, ..)
+            | Expr::Subscript(src, ..)
+            | Expr::Template(src, ..)
+            | Expr::Typeof(src, ..) => src,
+        }
+    }
+}
+
+// A `case` arm pattern: matched against the subject, with `Bind`/`List`
+// contributing names that are in scope for the arm's body
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    // A bare literal (number, string, etc.) or a capitalized/known-constant
+    // atom, matched against the subject for equality
+    Literal(Expr),
+    // `_`: matches anything, binds nothing
+    Wildcard,
+    // A fresh lowercase name: matches anything, binds the subject to it
+    Bind(String),
+    // `[a, b, ...tail]`; the optional second field is the rest pattern
+    List(Vec<Pattern>, Option<Box<Pattern>>),
+    // `pat if cond`: matches only if `pat` matches and `cond` is truthy,
+    // with `pat`'s bindings in scope for `cond`
+    Guard(Box<Pattern>, Expr),
+}
+
+#[derive(Debug, Clone)]
 pub enum Stmt {
     Assert(Src, Expr, String),
-    Case(Src, Expr, Vec<(Vec<Expr>, Vec<Stmt>)>),
+    Case(Src, Expr, Vec<(Vec<Pattern>, Vec<Stmt>)>),
     Dowhile(Src, Expr, Vec<Stmt>),
+    // A placeholder standing in for a statement the parser couldn't make
+    // sense of, inserted by error-recovery mode so the surrounding block
+    // still builds even though parsing as a whole failed
+    Error(Src),
     Expr(Src, Expr),
     For(Src, String, Expr, Vec<Stmt>),
     For2(Src, String, String, Expr, Vec<Stmt>),
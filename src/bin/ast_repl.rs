@@ -0,0 +1,46 @@
+// A REPL for inspecting the parser's output: reads one statement (or
+// block) at a time, using the same incomplete-input detection and
+// highlighting as src/bin/repl.rs, and pretty-prints the resulting
+// Vec<Stmt> instead of compiling or running it. Useful for debugging the
+// grammar itself, in the spirit of the AST-dump REPLs found in other
+// small-language front ends.
+use rustyline::error::ReadlineError;
+use rustyline::{Config, Editor};
+use std::cell::RefCell;
+use std::process;
+use std::rc::Rc;
+use verbena::*;
+
+fn main() {
+    // No evaluation happens here, so there are no variable names to feed
+    // completion; the helper still gets one to satisfy its constructor.
+    let names = Rc::new(RefCell::new(Vec::new()));
+
+    let config = Config::builder().auto_add_history(true).build();
+    let mut rl: Editor<ReplHelper, rustyline::history::DefaultHistory> =
+        Editor::with_config(config).expect("Failed to start line editor");
+    rl.set_helper(Some(ReplHelper::new(names)));
+
+    loop {
+        match rl.readline("ast> ") {
+            Ok(line) => {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match parse_str("<repl>", &line) {
+                    Ok(v) => println!("{:#?}", v),
+                    Err(errors) => {
+                        for e in &errors {
+                            eprintln!("{}", e);
+                        }
+                    }
+                }
+            }
+            Err(ReadlineError::Interrupted) | Err(ReadlineError::Eof) => break,
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        }
+    }
+}
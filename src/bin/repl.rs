@@ -0,0 +1,62 @@
+// Interactive front-end: reads one statement (or block) at a time and runs
+// it against a persistent VM, so `Dim`med globals and `fn` definitions from
+// earlier lines are still visible to later ones.
+use rustyline::error::ReadlineError;
+use rustyline::{Config, Editor};
+use std::cell::RefCell;
+use std::io::Cursor;
+use std::process;
+use std::rc::Rc;
+use verbena::*;
+
+fn refresh_names(vm: &VM, names: &Rc<RefCell<Vec<String>>>) {
+    let mut v: Vec<String> = vm.vars.keys().cloned().collect();
+    v.sort();
+    *names.borrow_mut() = v;
+}
+
+fn main() {
+    let mut vm = VM::new();
+    let names = Rc::new(RefCell::new(Vec::new()));
+    refresh_names(&vm, &names);
+
+    let config = Config::builder().auto_add_history(true).build();
+    let mut rl: Editor<ReplHelper, rustyline::history::DefaultHistory> =
+        Editor::with_config(config).expect("Failed to start line editor");
+    rl.set_helper(Some(ReplHelper::new(names.clone())));
+
+    loop {
+        match rl.readline("> ") {
+            Ok(line) => {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let reader = Cursor::new(line.into_bytes());
+                let ast = match parse("<repl>", reader) {
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        continue;
+                    }
+                    Ok(a) => a,
+                };
+                let program = match compile(&ast) {
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        continue;
+                    }
+                    Ok(p) => p,
+                };
+                match vm.run(program) {
+                    Ok(r) => println!("{}", r.inspect()),
+                    Err(e) => eprintln!("{}", e),
+                }
+                refresh_names(&vm, &names);
+            }
+            Err(ReadlineError::Interrupted) | Err(ReadlineError::Eof) => break,
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        }
+    }
+}
@@ -35,6 +35,290 @@ fn get_subdirs(dir: &str) -> Result<Vec<String>, io::Error> {
     Ok(subdirs)
 }
 
+// A single compiletest-style expectation parsed out of a `//~` annotation
+// comment: the program is required to raise an error on `line` whose
+// message contains `substring`.
+struct ExpectedError {
+    line: usize,
+    substring: String,
+}
+
+// Scans `text` for trailing `//~` annotation comments and returns the
+// `(line, substring)` pairs they describe.
+//
+// `//~ ERROR msg` annotates the line it appears on; `//~^ ERROR msg` (one or
+// more carets) points `n` lines above instead, for errors that are easier
+// to annotate from the following line (e.g. a missing closing token).
+fn collect_expected_errors(text: &str) -> Vec<ExpectedError> {
+    let mut expected = Vec::new();
+    for (i, line_text) in text.lines().enumerate() {
+        let marker = match line_text.find("//~") {
+            Some(pos) => pos,
+            None => continue,
+        };
+        let annotation = line_text[marker + 3..].trim_start();
+        let carets = annotation.chars().take_while(|&c| c == '^').count();
+        let annotation = annotation[carets..].trim_start();
+        let annotation = annotation.strip_prefix("ERROR").unwrap_or(annotation).trim_start();
+        expected.push(ExpectedError {
+            line: i + 1 - carets,
+            substring: annotation.to_string(),
+        });
+    }
+    expected
+}
+
+// Pulls `(line, message)` diagnostics out of the interpreter's stderr.
+//
+// Every error site in the crate renders its header the same way
+// `VError::format_error` does: `<file>:<line>:` optionally followed by
+// `<column>:`, then the message, so this is the one place that needs to
+// know that shape.
+fn parse_diagnostics(program_file: &str, stderr: &str) -> Vec<(usize, String)> {
+    let prefix = format!("{}:", program_file);
+    stderr
+        .lines()
+        .filter_map(|line| {
+            let rest = line.strip_prefix(prefix.as_str())?;
+            let (line_no, rest) = rest.split_once(':')?;
+            let line_no: usize = line_no.trim().parse().ok()?;
+            // CompileError's header has an extra `:<column>` before the
+            // message; strip it if present so both diagnostic shapes compare
+            // the same way.
+            let msg = match rest.split_once(':') {
+                Some((column, msg)) if column.trim().parse::<usize>().is_ok() => msg,
+                _ => rest,
+            };
+            Some((line_no, msg.trim().to_string()))
+        })
+        .collect()
+}
+
+// Checks that every annotated error was produced and that no unannotated
+// error occurred, returning a description of the mismatches if not.
+fn check_error_annotations(
+    program_file: &str,
+    expected: &[ExpectedError],
+    stderr: &str,
+) -> Result<(), String> {
+    let mut unmatched = parse_diagnostics(program_file, stderr);
+    let mut problems = Vec::new();
+
+    for e in expected {
+        match unmatched
+            .iter()
+            .position(|(line, msg)| *line == e.line && msg.contains(&e.substring))
+        {
+            Some(i) => {
+                unmatched.remove(i);
+            }
+            None => problems.push(format!(
+                "{}:{}: expected error containing {:?}, not produced",
+                program_file, e.line, e.substring
+            )),
+        }
+    }
+    for (line, msg) in unmatched {
+        problems.push(format!(
+            "{}:{}: unannotated error: {}",
+            program_file, line, msg
+        ));
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(problems.join("\n"))
+    }
+}
+
+// A single fenced ```verbena code block pulled out of a markdown file, with
+// whatever expectation annotations apply to it.
+struct DocBlock {
+    file: String,
+    // Line the opening fence is on, for diagnostics.
+    line: usize,
+    // Source to run, with `// Error: ...` annotation lines stripped out
+    // (they aren't valid Verbena syntax, just runner metadata).
+    code: String,
+    // ```verbena,should_fail
+    should_fail: bool,
+    expected_errors: Vec<String>,
+    // From an adjacent fenced block, if one immediately follows.
+    expected_output: Option<String>,
+}
+
+// Scans `text` for ```verbena fenced code blocks and returns one `DocBlock`
+// per block, the same way the example runner scans `.va` files for `//~`
+// annotations.
+fn collect_doc_blocks(file: &str, text: &str) -> Vec<DocBlock> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut blocks = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let trimmed = lines[i].trim_start();
+        let rest = match trimmed.strip_prefix("```verbena") {
+            Some(rest) => rest,
+            None => {
+                i += 1;
+                continue;
+            }
+        };
+        let should_fail = rest.trim_start_matches(',').trim() == "should_fail";
+        let line = i + 1;
+        i += 1;
+
+        let mut code_lines = Vec::new();
+        let mut expected_errors = Vec::new();
+        while i < lines.len() && lines[i].trim_end() != "```" {
+            match lines[i].trim_start().strip_prefix("// Error:") {
+                Some(msg) => expected_errors.push(msg.trim().to_string()),
+                None => code_lines.push(lines[i]),
+            }
+            i += 1;
+        }
+        i += 1; // past the closing fence
+
+        // An immediately following fenced block (in any other language, e.g.
+        // ```text) is this example's expected stdout, not a second example.
+        let mut expected_output = None;
+        let mut j = i;
+        if j < lines.len() && lines[j].trim().is_empty() {
+            j += 1;
+        }
+        if j < lines.len() {
+            let fence = lines[j].trim_start();
+            if fence.starts_with("```") && !fence.starts_with("```verbena") {
+                j += 1;
+                let mut out_lines = Vec::new();
+                while j < lines.len() && lines[j].trim_end() != "```" {
+                    out_lines.push(lines[j]);
+                    j += 1;
+                }
+                if j < lines.len() {
+                    let mut out = out_lines.join("\n");
+                    if !out_lines.is_empty() {
+                        out.push('\n');
+                    }
+                    expected_output = Some(out);
+                    i = j + 1;
+                }
+            }
+        }
+
+        blocks.push(DocBlock {
+            file: file.to_string(),
+            line,
+            code: code_lines.join("\n") + "\n",
+            should_fail,
+            expected_errors,
+            expected_output,
+        });
+    }
+    blocks
+}
+
+// Runs one doc block through the interpreter and checks it against its
+// annotations, the same subprocess-capture plumbing the `.va` example runner
+// uses. Returns a description of the mismatch, if any.
+fn run_doc_block(block: &DocBlock) -> Result<(), String> {
+    let tmp_path = format!("target/doctest-{}-{}.va", block.file.replace('/', "_"), block.line);
+    if let Err(e) = fs::write(&tmp_path, &block.code) {
+        return Err(format!(
+            "{}:{}: failed to write temp file: {}",
+            block.file, block.line, e
+        ));
+    }
+    let output = Command::new("./target/debug/verbena")
+        .arg(&tmp_path)
+        .output();
+    let _ = fs::remove_file(&tmp_path);
+    let output = output.map_err(|e| {
+        format!(
+            "{}:{}: failed to run interpreter: {}",
+            block.file, block.line, e
+        )
+    })?;
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+    let failed = output.status.code() != Some(0);
+
+    // A `VError` is expected whenever the block is tagged `should_fail` or
+    // annotates at least one `// Error: ...` substring to look for.
+    if block.should_fail || !block.expected_errors.is_empty() {
+        if !failed {
+            return Err(format!(
+                "{}:{}: expected this block to fail",
+                block.file, block.line
+            ));
+        }
+        for substring in &block.expected_errors {
+            if !stderr.contains(substring.as_str()) {
+                return Err(format!(
+                    "{}:{}: expected error containing {:?}, got:\n{}",
+                    block.file, block.line, substring, stderr
+                ));
+            }
+        }
+        return Ok(());
+    }
+
+    if failed {
+        return Err(format!(
+            "{}:{}: unexpected failure:\n{}",
+            block.file, block.line, stderr
+        ));
+    }
+    if let Some(expected) = &block.expected_output {
+        if &stdout != expected {
+            return Err(format!(
+                "{}:{}: output doesn't match expected.\nExpected:\n{}\nActual:\n{}",
+                block.file, block.line, expected, stdout
+            ));
+        }
+    }
+    Ok(())
+}
+
+// Walks every `.md` file in the repo root, runs each ```verbena block it
+// contains, and returns `(passed, total)`. This keeps language-guide
+// examples honest the same way the `examples/` directory keeps whole
+// programs honest.
+fn run_markdown_doctests() -> (usize, usize) {
+    let mut md_files = Vec::new();
+    if let Ok(entries) = fs::read_dir(".") {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) == Some("md") {
+                if let Some(s) = path.to_str() {
+                    md_files.push(s.to_string());
+                }
+            }
+        }
+    }
+    md_files.sort();
+
+    let mut passed = 0;
+    let mut total = 0;
+    for file in md_files {
+        let text = match fs::read_to_string(&file) {
+            Ok(t) => t,
+            Err(e) => {
+                println!("Failed to read {}: {}", file, e);
+                continue;
+            }
+        };
+        for block in collect_doc_blocks(&file, &text) {
+            total += 1;
+            match run_doc_block(&block) {
+                Ok(()) => passed += 1,
+                Err(msg) => println!("{}", msg),
+            }
+        }
+    }
+    (passed, total)
+}
+
 fn main() {
     // Get a list of the example programs
     let dirs = match get_subdirs("examples") {
@@ -50,6 +334,61 @@ fn main() {
 
     // For each example program
     for name in dirs {
+        let program_file = PathBuf::from("examples")
+            .join(&name)
+            .join(format!("{}.va", name))
+            .into_os_string()
+            .into_string()
+            .expect("Path contains invalid UTF-8");
+
+        let source = match fs::read_to_string(&program_file) {
+            Ok(s) => s,
+            Err(_) => {
+                skipped_count += 1;
+                continue;
+            }
+        };
+        let expected_errors = collect_expected_errors(&source);
+
+        // An annotated example is a negative test: it must fail, and must
+        // fail with exactly the diagnostics it names, nothing more.
+        if !expected_errors.is_empty() {
+            let output = match Command::new("./target/debug/verbena")
+                .arg(&program_file)
+                .output()
+            {
+                Ok(output) => output,
+                Err(e) => {
+                    println!("{}", program_file);
+                    println!("Failed to run interpreter: {}", e);
+                    exit(1);
+                }
+            };
+            let stderr_output = match String::from_utf8(output.stderr) {
+                Ok(string) => string,
+                Err(e) => {
+                    println!("{}", program_file);
+                    println!("stderr output not valid UTF-8: {}", e);
+                    exit(1);
+                }
+            };
+
+            if output.status.code() == Some(0) {
+                println!("{}", program_file);
+                println!("Expected a nonzero exit code, since errors are annotated");
+                continue;
+            }
+
+            match check_error_annotations(&program_file, &expected_errors, &stderr_output) {
+                Ok(()) => passed_count += 1,
+                Err(problems) => {
+                    println!("{}", program_file);
+                    println!("{}", problems);
+                }
+            }
+            continue;
+        }
+
         // If output.txt exists, use it as the basis for comparison
         let expected_output_file = PathBuf::from("examples").join(&name).join("output.txt");
         if !expected_output_file.exists() {
@@ -69,12 +408,6 @@ fn main() {
         };
 
         // Run the program
-        let program_file = PathBuf::from("examples")
-            .join(&name)
-            .join(format!("{}.va", name))
-            .into_os_string()
-            .into_string()
-            .expect("Path contains invalid UTF-8");
         let output = match Command::new("./target/debug/verbena")
             .arg(&program_file)
             .output()
@@ -130,4 +463,7 @@ fn main() {
     }
     println!("Passed : {}", passed_count);
     println!("Skipped: {}", skipped_count);
+
+    let (doc_passed, doc_total) = run_markdown_doctests();
+    println!("Doc examples passed: {}/{}", doc_passed, doc_total);
 }
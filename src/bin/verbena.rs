@@ -1,55 +1,35 @@
+// KNOWN GAP: there is no lowering pass from the parsed AST to a
+// program::Program anywhere in this crate. `compiler::compile` is a
+// different function entirely (it emits JavaScript to an output file, not
+// a Program); nothing turns an `Ast` into the `Inst` sequence `VM::run`
+// expects. Bytecode/VM support (program.rs/vm.rs/optimize.rs and
+// everything registered on `VM`) is reachable only from hand-built `Inst`
+// vectors in tests, never from an actual Verbena source file, and should
+// be treated as a known-unreachable subsystem rather than a finished
+// feature until that lowering pass is written. This binary stops short of
+// that and reports the gap instead of calling through functions that
+// don't exist. For a working CLI, use the JS-transpiler binary built from
+// src/main.rs.
 use std::env;
-use std::fs;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
 use std::process;
-use verbena::*;
+use verbena::parse_or_exit;
 
 fn main() {
-    // Get command line arguments
     let args: Vec<String> = env::args().collect();
-
-    // Check if a filename was provided
     if args.len() < 2 {
         eprintln!("Usage: {} <file>", args[0]);
         process::exit(1);
     }
-
-    // Get the filename from command line arguments
     let file = &args[1];
 
-    // Open the file
-    let f = match File::open(file) {
-        Ok(a) => a,
-        Err(e) => {
-            eprintln!("{}: {}", file, e);
-            process::exit(1);
-        }
-    };
-
-    // Parse
-    let reader = BufReader::new(f);
-    let ast = match parse(file, reader) {
-        Err(e) => {
-            eprintln!("{}", e);
-            process::exit(1);
-        }
-        Ok(a) => a,
-    };
+    // Parsing itself is real and works; it's the next step that doesn't exist.
+    let _ast = parse_or_exit(file);
 
-    // Compile to VM instructions
-    let program = match compile(&ast) {
-        Err(e) => {
-            eprintln!("{}", e);
-            process::exit(1);
-        }
-        Ok(a) => a,
-    };
-
-    // Run
-    let mut vm = VM::new();
-    if let Err(e) = vm.run(program) {
-        eprintln!("{}", e);
-        process::exit(1);
-    }
+    eprintln!(
+        "{}: no AST-to-Program lowering pass exists yet, so this binary can't \
+         run the bytecode VM on parsed source; see the comment at the top of \
+         this file",
+        file
+    );
+    process::exit(1);
 }
@@ -68,3 +68,269 @@ impl std::fmt::Debug for FuncDef {
         Ok(())
     }
 }
+
+// Magic bytes at the start of a serialized FuncDef, so a stale or unrelated file is rejected
+// up front instead of producing a confusing decode error partway through.
+const MAGIC: &[u8; 4] = b"VBC1";
+
+fn write_u32(buf: &mut Vec<u8>, n: u32) {
+    buf.extend_from_slice(&n.to_le_bytes());
+}
+
+fn write_str(buf: &mut Vec<u8>, s: &str) {
+    write_u32(buf, s.len() as u32);
+    buf.extend_from_slice(s.as_bytes());
+}
+
+fn read_u32(buf: &[u8], pos: &mut usize) -> Result<u32, String> {
+    if *pos + 4 > buf.len() {
+        return Err("Truncated bytecode".to_string());
+    }
+    let n = u32::from_le_bytes(buf[*pos..*pos + 4].try_into().unwrap());
+    *pos += 4;
+    Ok(n)
+}
+
+fn read_usize(buf: &[u8], pos: &mut usize) -> Result<usize, String> {
+    Ok(read_u32(buf, pos)? as usize)
+}
+
+fn read_str(buf: &[u8], pos: &mut usize) -> Result<String, String> {
+    let n = read_usize(buf, pos)?;
+    if *pos + n > buf.len() {
+        return Err("Truncated bytecode".to_string());
+    }
+    let s = match String::from_utf8(buf[*pos..*pos + n].to_vec()) {
+        Ok(s) => s,
+        Err(e) => return Err(e.to_string()),
+    };
+    *pos += n;
+    Ok(s)
+}
+
+// Only the value-semantics variants can round-trip through bytecode;
+// closures and other reference-semantics values are runtime-only.
+fn write_val(buf: &mut Vec<u8>, a: &Val) -> Result<(), String> {
+    match a {
+        Val::True => buf.push(0),
+        Val::False => buf.push(1),
+        Val::Null => buf.push(2),
+        Val::Num(a) => {
+            buf.push(3);
+            buf.extend_from_slice(&a.to_le_bytes());
+        }
+        Val::Str(s) => {
+            buf.push(4);
+            write_str(buf, s);
+        }
+        _ => return Err("Value is not serializable".to_string()),
+    }
+    Ok(())
+}
+
+fn read_val(buf: &[u8], pos: &mut usize) -> Result<Val, String> {
+    if *pos >= buf.len() {
+        return Err("Truncated bytecode".to_string());
+    }
+    let tag = buf[*pos];
+    *pos += 1;
+    let r = match tag {
+        0 => Val::True,
+        1 => Val::False,
+        2 => Val::Null,
+        3 => {
+            if *pos + 8 > buf.len() {
+                return Err("Truncated bytecode".to_string());
+            }
+            let a = f64::from_le_bytes(buf[*pos..*pos + 8].try_into().unwrap());
+            *pos += 8;
+            Val::Num(a)
+        }
+        4 => Val::Str(read_str(buf, pos)?),
+        _ => return Err(format!("Unknown value tag {}", tag)),
+    };
+    Ok(r)
+}
+
+fn write_inst(buf: &mut Vec<u8>, a: &Inst) -> Result<(), String> {
+    match a {
+        Inst::Const(a) => {
+            buf.push(0);
+            write_val(buf, a)?;
+        }
+        Inst::Pop => buf.push(1),
+        Inst::LoadGlobal(s) => {
+            buf.push(2);
+            write_str(buf, s);
+        }
+        Inst::StoreGlobal(s) => {
+            buf.push(3);
+            write_str(buf, s);
+        }
+        Inst::StoreAt => buf.push(4),
+        Inst::Br(i) => {
+            buf.push(5);
+            write_u32(buf, *i as u32);
+        }
+        Inst::BrTrue(i) => {
+            buf.push(6);
+            write_u32(buf, *i as u32);
+        }
+        Inst::BrFalse(i) => {
+            buf.push(7);
+            write_u32(buf, *i as u32);
+        }
+        Inst::DupBrTrue(i) => {
+            buf.push(8);
+            write_u32(buf, *i as u32);
+        }
+        Inst::DupBrFalse(i) => {
+            buf.push(9);
+            write_u32(buf, *i as u32);
+        }
+        Inst::Return => buf.push(10),
+        Inst::Exit => buf.push(11),
+        Inst::Add => buf.push(12),
+        Inst::Sub => buf.push(13),
+        Inst::Mul => buf.push(14),
+        Inst::IDiv => buf.push(15),
+        Inst::Div => buf.push(16),
+        Inst::Mod => buf.push(17),
+        Inst::Shl => buf.push(18),
+        Inst::LShr => buf.push(19),
+        Inst::Shr => buf.push(20),
+        Inst::BitAnd => buf.push(21),
+        Inst::BitOr => buf.push(22),
+        Inst::BitXor => buf.push(23),
+        Inst::BitNot => buf.push(24),
+        Inst::Neg => buf.push(25),
+        Inst::Not => buf.push(26),
+        Inst::Eq => buf.push(27),
+        Inst::Ne => buf.push(28),
+        Inst::Lt => buf.push(29),
+        Inst::Gt => buf.push(30),
+        Inst::Le => buf.push(31),
+        Inst::Ge => buf.push(32),
+        Inst::Pow => buf.push(33),
+        Inst::Assert(s) => {
+            buf.push(34);
+            write_str(buf, s);
+        }
+        Inst::Call(n) => {
+            buf.push(35);
+            write_u32(buf, *n as u32);
+        }
+        Inst::Object(n) => {
+            buf.push(36);
+            write_u32(buf, *n as u32);
+        }
+        Inst::List(n) => {
+            buf.push(37);
+            write_u32(buf, *n as u32);
+        }
+        Inst::Subscript => buf.push(38),
+        Inst::Dup2Subscript => buf.push(39),
+        Inst::Slice => buf.push(40),
+        Inst::Prin => buf.push(41),
+    }
+    Ok(())
+}
+
+fn read_inst(buf: &[u8], pos: &mut usize) -> Result<Inst, String> {
+    if *pos >= buf.len() {
+        return Err("Truncated bytecode".to_string());
+    }
+    let tag = buf[*pos];
+    *pos += 1;
+    let r = match tag {
+        0 => Inst::Const(read_val(buf, pos)?),
+        1 => Inst::Pop,
+        2 => Inst::LoadGlobal(read_str(buf, pos)?),
+        3 => Inst::StoreGlobal(read_str(buf, pos)?),
+        4 => Inst::StoreAt,
+        5 => Inst::Br(read_usize(buf, pos)?),
+        6 => Inst::BrTrue(read_usize(buf, pos)?),
+        7 => Inst::BrFalse(read_usize(buf, pos)?),
+        8 => Inst::DupBrTrue(read_usize(buf, pos)?),
+        9 => Inst::DupBrFalse(read_usize(buf, pos)?),
+        10 => Inst::Return,
+        11 => Inst::Exit,
+        12 => Inst::Add,
+        13 => Inst::Sub,
+        14 => Inst::Mul,
+        15 => Inst::IDiv,
+        16 => Inst::Div,
+        17 => Inst::Mod,
+        18 => Inst::Shl,
+        19 => Inst::LShr,
+        20 => Inst::Shr,
+        21 => Inst::BitAnd,
+        22 => Inst::BitOr,
+        23 => Inst::BitXor,
+        24 => Inst::BitNot,
+        25 => Inst::Neg,
+        26 => Inst::Not,
+        27 => Inst::Eq,
+        28 => Inst::Ne,
+        29 => Inst::Lt,
+        30 => Inst::Gt,
+        31 => Inst::Le,
+        32 => Inst::Ge,
+        33 => Inst::Pow,
+        34 => Inst::Assert(read_str(buf, pos)?),
+        35 => Inst::Call(read_usize(buf, pos)?),
+        36 => Inst::Object(read_usize(buf, pos)?),
+        37 => Inst::List(read_usize(buf, pos)?),
+        38 => Inst::Subscript,
+        39 => Inst::Dup2Subscript,
+        40 => Inst::Slice,
+        41 => Inst::Prin,
+        _ => return Err(format!("Unknown instruction tag {}", tag)),
+    };
+    Ok(r)
+}
+
+impl FuncDef {
+    // Serializes this function's bytecode and error contexts to a portable byte buffer
+    pub fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(MAGIC);
+
+        write_u32(&mut buf, self.insts.len() as u32);
+        for a in &self.insts {
+            write_inst(&mut buf, a)?;
+        }
+
+        write_u32(&mut buf, self.ecs.len() as u32);
+        for ec in &self.ecs {
+            write_str(&mut buf, &ec.file);
+            write_u32(&mut buf, ec.line as u32);
+        }
+
+        Ok(buf)
+    }
+
+    // Reloads a function previously written by `to_bytes`
+    pub fn from_bytes(buf: &[u8]) -> Result<Self, String> {
+        if buf.len() < MAGIC.len() || &buf[..MAGIC.len()] != MAGIC {
+            return Err("Not a verbena bytecode file".to_string());
+        }
+        let mut pos = MAGIC.len();
+
+        let n = read_usize(buf, &mut pos)?;
+        let mut insts = Vec::with_capacity(n);
+        for _ in 0..n {
+            insts.push(read_inst(buf, &mut pos)?);
+        }
+
+        let n = read_usize(buf, &mut pos)?;
+        let mut ecs = Vec::with_capacity(n);
+        for _ in 0..n {
+            let file = read_str(buf, &mut pos)?;
+            let line = read_usize(buf, &mut pos)?;
+            ecs.push(ErrorContext { file, line });
+        }
+
+        Ok(FuncDef { insts, ecs })
+    }
+}
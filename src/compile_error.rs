@@ -4,23 +4,54 @@ use std::fmt;
 pub struct CompileError {
     pub file: String,
     pub line: usize,
+    pub column: usize,
     pub message: String,
+
+    // The source line the error occurred on, kept so Display can render a caret snippet
+    // without needing the whole source text around at print time
+    pub snippet: String,
 }
 
 impl CompileError {
-    fn new(file: &str, text: &Vec<char>, start: usize, message: String) -> Self {
+    // Line/column and the caret snippet are derived lazily from `text` and `start`
+    // rather than tracked incrementally by the tokenizer, so callers only need
+    // to carry a flat offset around.
+    pub(crate) fn new(file: &str, text: &Vec<char>, start: usize, message: String) -> Self {
         // Calculate line number by counting newlines up to start position
         let line = text[..start].iter().filter(|&&c| c == '\n').count() + 1;
+
+        // Find the start of the line containing `start`, to compute the column
+        // and extract the snippet to show under the caret
+        let line_start = text[..start]
+            .iter()
+            .rposition(|&c| c == '\n')
+            .map_or(0, |pos| pos + 1);
+        let column = start - line_start;
+
+        let line_end = text[start..]
+            .iter()
+            .position(|&c| c == '\n')
+            .map_or(text.len(), |pos| start + pos);
+        let snippet: String = text[line_start..line_end].iter().collect();
+
         CompileError {
             file: file.to_string(),
             line,
+            column,
             message,
+            snippet,
         }
     }
 }
 
 impl fmt::Display for CompileError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}:{}: {}", self.file, self.line, self.message)
+        writeln!(
+            f,
+            "{}:{}:{}: {}",
+            self.file, self.line, self.column, self.message
+        )?;
+        writeln!(f, "{}", self.snippet)?;
+        write!(f, "{}^", " ".repeat(self.column))
     }
 }
@@ -1,73 +1,235 @@
 use crate::ast::*;
+use crate::error::Error;
 use std::collections::HashSet;
 use std::fs::File;
 use std::io::Write;
-use std::process;
+use std::path::Path;
 
 const PREFIX_JS_BYTES: &[u8] = include_bytes!("prefix.js");
 
-fn emit(out: &mut File, b: &[u8]) {
-    match out.write_all(b) {
-        Ok(_) => {}
-        Err(e) => {
-            eprintln!("{}", e);
-            process::exit(1);
+const BASE64_ALPHABET: &[u8] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// Encodes `value` as a Base64 VLQ segment (Source Map v3's encoding for
+// mapping deltas): the number is shifted left one bit with the sign moved
+// into bit 0, then emitted as 5-bit groups from least to most significant,
+// with the continuation bit set on every group but the last.
+fn encode_vlq(out: &mut String, value: i64) {
+    let mut n = if value < 0 {
+        ((-value as u64) << 1) | 1
+    } else {
+        (value as u64) << 1
+    };
+    loop {
+        let mut digit = (n & 0x1f) as u8;
+        n >>= 5;
+        if n > 0 {
+            digit |= 0x20;
+        }
+        out.push(BASE64_ALPHABET[digit as usize] as char);
+        if n == 0 {
+            break;
+        }
+    }
+}
+
+// One recorded mapping: the generated position `emit()` was at when a
+// `Stmt` carrying a `_src` started, and the 0-based source line it came
+// from. There's only ever one source file, so sourceIndex and
+// sourceColumn are always 0.
+struct Segment {
+    gen_line: usize,
+    gen_col: usize,
+    src_line: usize,
+}
+
+// Tracks the position `emit()` has written up to in the generated output,
+// and accumulates one `Segment` per statement that carries a `_src`, so the
+// whole thing can be encoded as a Source Map v3 `mappings` string once
+// compilation finishes.
+struct SourceMap {
+    segments: Vec<Segment>,
+    gen_line: usize,
+    gen_col: usize,
+}
+
+impl SourceMap {
+    fn new() -> Self {
+        SourceMap {
+            segments: Vec::new(),
+            gen_line: 0,
+            gen_col: 0,
+        }
+    }
+
+    // Called with every byte slice written to the output, to keep
+    // `gen_line`/`gen_col` in sync with what's actually on disk.
+    fn advance(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            if b == b'\n' {
+                self.gen_line += 1;
+                self.gen_col = 0;
+            } else {
+                self.gen_col += 1;
+            }
+        }
+    }
+
+    fn mark(&mut self, src: &Src) {
+        self.segments.push(Segment {
+            gen_line: self.gen_line,
+            gen_col: self.gen_col,
+            src_line: src.line.saturating_sub(1),
+        });
+    }
+
+    // Encodes the recorded segments as Source Map v3's semicolon-per-
+    // generated-line, comma-separated-segment `mappings` string.
+    //
+    // genColumn deltas reset to absolute at the start of each generated
+    // line; sourceLine (and the always-zero sourceIndex/sourceColumn)
+    // deltas accumulate across the whole mapping, per the spec.
+    fn encode_mappings(&self) -> String {
+        let mut out = String::new();
+        let mut cur_line = 0;
+        let mut first_on_line = true;
+        let mut prev_gen_col = 0i64;
+        let mut prev_src_line = 0i64;
+
+        for seg in &self.segments {
+            while cur_line < seg.gen_line {
+                out.push(';');
+                cur_line += 1;
+                prev_gen_col = 0;
+                first_on_line = true;
+            }
+            if !first_on_line {
+                out.push(',');
+            }
+            first_on_line = false;
+
+            encode_vlq(&mut out, seg.gen_col as i64 - prev_gen_col);
+            encode_vlq(&mut out, 0); // sourceIndex delta: always source 0
+            encode_vlq(&mut out, seg.src_line as i64 - prev_src_line);
+            encode_vlq(&mut out, 0); // sourceColumn delta: not tracked
+
+            prev_gen_col = seg.gen_col as i64;
+            prev_src_line = seg.src_line as i64;
         }
+        out
     }
+
+    // Renders the whole thing as a Source Map v3 JSON document.
+    fn to_json(&self, source_file: &str) -> String {
+        format!(
+            "{{\"version\":3,\"sources\":[{}],\"names\":[],\"mappings\":\"{}\"}}",
+            json_string(source_file),
+            self.encode_mappings()
+        )
+    }
+}
+
+// Minimal JSON string escaping; source paths won't usually need more than
+// quotes and backslashes escaped, but control characters are handled too.
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn emit(out: &mut File, map: &mut SourceMap, b: &[u8]) -> Result<(), Error> {
+    out.write_all(b)?;
+    map.advance(b);
+    Ok(())
 }
 
 // Compiler is instantiated separately for each nested function
 struct Compiler<'a> {
     assigned: HashSet<String>,
     out: &'a mut File,
+    map: &'a mut SourceMap,
 }
 
 impl<'a> Compiler<'a> {
-    fn new(out: &'a mut File) -> Self {
+    fn new(out: &'a mut File, map: &'a mut SourceMap) -> Self {
         Compiler {
             assigned: HashSet::<String>::new(),
             out,
+            map,
         }
     }
 
-    fn emit(&mut self, s: &str) {
-        emit(self.out, s.as_bytes());
+    fn emit(&mut self, s: &str) -> Result<(), Error> {
+        emit(self.out, self.map, s.as_bytes())
     }
 
     // Declare variables
     fn decl_expr(&mut self, a: &Expr) {
         match a {
-            Expr::Call(f, args) => {
+            Expr::Call(_, f, args) => {
                 self.decl_expr(f);
                 for a in args {
                     self.decl_expr(a);
                 }
             }
-            Expr::List(v) | Expr::Object(v) => {
+            Expr::List(_, v) | Expr::Object(_, v) => {
                 for a in v {
                     self.decl_expr(a);
                 }
             }
-            Expr::Slice(a, i, j) => {
+            Expr::Slice(_, a, i, j) => {
                 self.decl_expr(a);
                 self.decl_expr(i);
                 self.decl_expr(j);
             }
-            Expr::Subscript(a, b) | Expr::Infix(_, a, b) => {
+            Expr::Subscript(_, a, b) | Expr::Infix(_, _, a, b) => {
                 self.decl_expr(a);
                 self.decl_expr(b);
             }
-            Expr::Prefix(_, a) | Expr::Typeof(a) => {
+            Expr::Prefix(_, _, a) | Expr::Typeof(_, a) => {
                 self.decl_expr(a);
             }
-            Expr::Assign(a, b) => {
-                if let Expr::Atom(name) = &**a {
+            Expr::Assign(_, a, b) => {
+                if let Expr::Atom(_, name) = &**a {
                     self.assigned.insert(name.to_string());
                 }
                 self.decl_expr(a);
                 self.decl_expr(b);
             }
-            Expr::Atom(_) => {}
+            Expr::Template(_, pieces) => {
+                for a in pieces {
+                    self.decl_expr(a);
+                }
+            }
+            // A lambda's own params get a fresh binding from the JS arrow
+            // function itself, so they're excluded from the names bubbled
+            // up; anything else it assigns is captured from (and so must
+            // still be hoisted by) the enclosing scope
+            Expr::Lambda(_, params, body) => {
+                let outer = std::mem::take(&mut self.assigned);
+                self.decl_expr(body);
+                let inner = std::mem::replace(&mut self.assigned, outer);
+                self.assigned
+                    .extend(inner.into_iter().filter(|a| !params.contains(a)));
+            }
+            Expr::LambdaBlock(_, params, body) => {
+                let outer = std::mem::take(&mut self.assigned);
+                self.decl_block(body);
+                let inner = std::mem::replace(&mut self.assigned, outer);
+                self.assigned
+                    .extend(inner.into_iter().filter(|a| !params.contains(a)));
+            }
+            Expr::Atom(_, _) | Expr::Error(_) => {}
         }
     }
 
@@ -104,12 +266,12 @@ impl<'a> Compiler<'a> {
                 self.decl_expr(subject);
                 for (patterns, body) in cases {
                     for pattern in patterns {
-                        self.decl_expr(pattern);
+                        self.decl_pattern(pattern);
                     }
                     self.decl_block(body);
                 }
             }
-            Stmt::Label(_, _) | Stmt::Func(_, _, _, _, _) => {}
+            Stmt::Error(_) | Stmt::Label(_, _) | Stmt::Func(_, _, _, _, _) => {}
         }
     }
 
@@ -119,258 +281,436 @@ impl<'a> Compiler<'a> {
         }
     }
 
+    // Declares the names a `case` pattern binds, same as `decl_expr` does
+    // for plain assignment
+    fn decl_pattern(&mut self, p: &Pattern) {
+        match p {
+            Pattern::Literal(a) => self.decl_expr(a),
+            Pattern::Wildcard => {}
+            Pattern::Bind(name) => {
+                self.assigned.insert(name.to_string());
+            }
+            Pattern::List(elems, rest) => {
+                for e in elems {
+                    self.decl_pattern(e);
+                }
+                if let Some(r) = rest {
+                    self.decl_pattern(r);
+                }
+            }
+            Pattern::Guard(p, cond) => {
+                self.decl_pattern(p);
+                self.decl_expr(cond);
+            }
+        }
+    }
+
+    // Emits a JS boolean expression testing whether `subject` (a JS
+    // expression, already evaluated at most once by the caller) matches
+    // `pattern`, assigning any names it binds along the way
+    fn pattern_match(&mut self, subject: &str, pattern: &Pattern) -> Result<(), Error> {
+        match pattern {
+            Pattern::Literal(a) => {
+                self.emit(subject)?;
+                self.emit("===")?;
+                self.expr(a)?;
+            }
+            Pattern::Wildcard => {
+                self.emit("true")?;
+            }
+            Pattern::Bind(name) => {
+                self.emit("(")?;
+                self.emit(name)?;
+                self.emit("=")?;
+                self.emit(subject)?;
+                self.emit(",true)")?;
+            }
+            Pattern::List(elems, rest) => {
+                self.emit("(Array.isArray(")?;
+                self.emit(subject)?;
+                self.emit(")&&")?;
+                self.emit(subject)?;
+                self.emit(".length")?;
+                self.emit(if rest.is_some() { ">=" } else { "===" })?;
+                self.emit(&elems.len().to_string())?;
+                for (i, e) in elems.iter().enumerate() {
+                    self.emit("&&(")?;
+                    self.pattern_match(&format!("{}[{}]", subject, i), e)?;
+                    self.emit(")")?;
+                }
+                if let Some(r) = rest {
+                    self.emit("&&(")?;
+                    self.pattern_match(&format!("{}.slice({})", subject, elems.len()), r)?;
+                    self.emit(")")?;
+                }
+                self.emit(")")?;
+            }
+            Pattern::Guard(p, cond) => {
+                self.emit("(")?;
+                self.pattern_match(subject, p)?;
+                self.emit("&&")?;
+                self.expr(cond)?;
+                self.emit(")")?;
+            }
+        }
+        Ok(())
+    }
+
     // Generate code
-    fn expr(&mut self, a: &Expr) {
+    fn expr(&mut self, a: &Expr) -> Result<(), Error> {
         match a {
-            Expr::Atom(s) => {
-                self.emit(s);
+            Expr::Atom(_, s) => {
+                self.emit(s)?;
             }
-            Expr::Call(f, args) => {
-                self.expr(f);
-                self.emit("(");
+            Expr::Call(_, f, args) => {
+                self.expr(f)?;
+                self.emit("(")?;
                 for (i, a) in args.iter().enumerate() {
                     if 0 < i {
-                        self.emit(",");
+                        self.emit(",")?;
                     }
-                    self.expr(a);
+                    self.expr(a)?;
                 }
-                self.emit(")");
+                self.emit(")")?;
             }
-            Expr::List(v) => {
-                self.emit("[");
+            Expr::Error(_) => {
+                self.emit("undefined")?;
+            }
+            Expr::List(_, v) => {
+                self.emit("[")?;
                 for (i, a) in v.iter().enumerate() {
                     if 0 < i {
-                        self.emit(",");
+                        self.emit(",")?;
                     }
-                    self.expr(a);
+                    self.expr(a)?;
                 }
-                self.emit("]");
+                self.emit("]")?;
             }
-            Expr::Object(v) => {
-                self.emit("new Map([");
+            Expr::Object(_, v) => {
+                self.emit("new Map([")?;
                 for i in (0..v.len()).step_by(2) {
                     if 0 < i {
-                        self.emit(",");
+                        self.emit(",")?;
+                    }
+                    self.emit("[")?;
+                    self.expr(&v[i])?;
+                    self.emit(",")?;
+                    self.expr(&v[i + 1])?;
+                    self.emit("]")?;
+                }
+                self.emit("])")?;
+            }
+            Expr::Subscript(_, a, i) => {
+                self.emit("_get(")?;
+                self.expr(a)?;
+                self.emit(",")?;
+                self.expr(i)?;
+                self.emit(")")?;
+            }
+            Expr::Slice(_, a, i, j) => {
+                self.expr(a)?;
+                self.emit(".slice(")?;
+                self.expr(i)?;
+                self.emit(",")?;
+                self.expr(j)?;
+                self.emit(")")?;
+            }
+            Expr::Infix(_, s, a, b) => {
+                self.emit("(")?;
+                self.expr(a)?;
+                self.emit(s)?;
+                self.expr(b)?;
+                self.emit(")")?;
+            }
+            Expr::Prefix(_, s, a) => {
+                self.emit("(")?;
+                self.emit(s)?;
+                self.expr(a)?;
+                self.emit(")")?;
+            }
+            Expr::Typeof(_, a) => {
+                self.emit("_typeof(")?;
+                self.expr(a)?;
+                self.emit(")")?;
+            }
+            Expr::Template(_, pieces) => {
+                self.emit("(")?;
+                for (i, a) in pieces.iter().enumerate() {
+                    if 0 < i {
+                        self.emit("+")?;
                     }
-                    self.emit("[");
-                    self.expr(&v[i]);
-                    self.emit(",");
-                    self.expr(&v[i + 1]);
-                    self.emit("]");
+                    self.expr(a)?;
+                }
+                self.emit(")")?;
+            }
+            // Unlike `Stmt::Func`, no names are hoisted as fresh locals
+            // here: a bare assignment to a non-parameter name resolves
+            // through the JS scope chain to whatever enclosing binding
+            // `decl_expr` already arranged to hoist there, giving lambdas
+            // their capture-by-default semantics for free
+            Expr::Lambda(_, params, body) => {
+                self.emit("(")?;
+                self.emit(&params.join(","))?;
+                self.emit(")=>{\n")?;
+                for p in params {
+                    self.emit("if (")?;
+                    self.emit(p)?;
+                    self.emit("=== undefined)")?;
+                    self.emit(p)?;
+                    self.emit("= null;\n")?;
+                }
+                self.emit("return (")?;
+                self.expr(body)?;
+                self.emit(")\n}")?;
+            }
+            Expr::LambdaBlock(_, params, body) => {
+                self.emit("(")?;
+                self.emit(&params.join(","))?;
+                self.emit(")=>{\n")?;
+                for p in params {
+                    self.emit("if (")?;
+                    self.emit(p)?;
+                    self.emit("=== undefined)")?;
+                    self.emit(p)?;
+                    self.emit("= null;\n")?;
                 }
-                self.emit("])");
-            }
-            Expr::Subscript(a, i) => {
-                self.emit("_get(");
-                self.expr(a);
-                self.emit(",");
-                self.expr(i);
-                self.emit(")");
-            }
-            Expr::Slice(a, i, j) => {
-                self.expr(a);
-                self.emit(".slice(");
-                self.expr(i);
-                self.emit(",");
-                self.expr(j);
-                self.emit(")");
-            }
-            Expr::Infix(s, a, b) => {
-                self.emit("(");
-                self.expr(a);
-                self.emit(s);
-                self.expr(b);
-                self.emit(")");
-            }
-            Expr::Prefix(s, a) => {
-                self.emit("(");
-                self.emit(s);
-                self.expr(a);
-                self.emit(")");
-            }
-            Expr::Typeof(a) => {
-                self.emit("_typeof(");
-                self.expr(a);
-                self.emit(")");
-            }
-            Expr::Assign(a, b) => match &**a {
-                Expr::Subscript(a, i) => {
-                    self.emit("_set(");
-                    self.expr(a);
-                    self.emit(",");
-                    self.expr(i);
-                    self.emit(",");
-                    self.expr(b);
-                    self.emit(")");
+                self.block(body, true)?;
+                self.emit("return null\n")?;
+                self.emit("}")?;
+            }
+            Expr::Assign(_, a, b) => match &**a {
+                Expr::Subscript(_, a, i) => {
+                    self.emit("_set(")?;
+                    self.expr(a)?;
+                    self.emit(",")?;
+                    self.expr(i)?;
+                    self.emit(",")?;
+                    self.expr(b)?;
+                    self.emit(")")?;
                 }
                 _ => {
-                    self.expr(a);
-                    self.emit("=");
-                    self.expr(b);
+                    self.expr(a)?;
+                    self.emit("=")?;
+                    self.expr(b)?;
                 }
             },
         }
+        Ok(())
     }
 
-    fn stmt(&mut self, a: &Stmt, last: bool) {
+    fn stmt(&mut self, a: &Stmt, last: bool) -> Result<(), Error> {
+        let src = match a {
+            Stmt::Assert(src, ..)
+            | Stmt::Case(src, ..)
+            | Stmt::Dowhile(src, ..)
+            | Stmt::Error(src, ..)
+            | Stmt::Expr(src, ..)
+            | Stmt::For(src, ..)
+            | Stmt::For2(src, ..)
+            | Stmt::Func(src, ..)
+            | Stmt::If(src, ..)
+            | Stmt::Label(src, ..)
+            | Stmt::Return(src, ..)
+            | Stmt::Throw(src, ..)
+            | Stmt::Try(src, ..)
+            | Stmt::While(src, ..) => src,
+        };
+        self.map.mark(src);
         match a {
             Stmt::If(_src, cond, yes, no) => {
-                self.emit("if (");
-                self.expr(cond);
-                self.emit(") {\n");
-                self.block(yes, last);
-                self.emit("} else {\n");
-                self.block(no, last);
-                self.emit("}\n");
+                self.emit("if (")?;
+                self.expr(cond)?;
+                self.emit(") {\n")?;
+                self.block(yes, last)?;
+                self.emit("} else {\n")?;
+                self.block(no, last)?;
+                self.emit("}\n")?;
             }
             Stmt::Try(_src, normal, name, fallback) => {
-                self.emit("try {\n");
-                self.block(normal, last);
-                self.emit("} catch (");
-                self.emit(name);
-                self.emit(") {\n");
-                self.block(fallback, last);
-                self.emit("}\n");
+                self.emit("try {\n")?;
+                self.block(normal, last)?;
+                self.emit("} catch (")?;
+                self.emit(name)?;
+                self.emit(") {\n")?;
+                self.block(fallback, last)?;
+                self.emit("}\n")?;
             }
             Stmt::Assert(_src, cond, msg) => {
-                self.emit("assert(");
-                self.expr(cond);
+                self.emit("assert(")?;
+                self.expr(cond)?;
                 if !msg.is_empty() {
-                    self.emit(",");
-                    self.emit(msg);
+                    self.emit(",")?;
+                    self.emit(msg)?;
                 }
-                self.emit(");\n");
+                self.emit(");\n")?;
+            }
+            Stmt::Error(_src) => {
+                self.emit(";\n")?;
             }
             Stmt::Label(_src, s) => {
-                self.emit(s);
-                self.emit(":\n");
+                self.emit(s)?;
+                self.emit(":\n")?;
             }
             Stmt::Dowhile(_src, cond, body) => {
-                self.emit("do {");
-                self.block(body, false);
-                self.emit("} while (");
-                self.expr(cond);
-                self.emit(");\n");
+                self.emit("do {")?;
+                self.block(body, false)?;
+                self.emit("} while (")?;
+                self.expr(cond)?;
+                self.emit(");\n")?;
             }
             Stmt::While(_src, cond, body) => {
-                self.emit("while (");
-                self.expr(cond);
-                self.emit(") {\n");
-                self.block(body, false);
-                self.emit("}\n");
+                self.emit("while (")?;
+                self.expr(cond)?;
+                self.emit(") {\n")?;
+                self.block(body, false)?;
+                self.emit("}\n")?;
             }
             Stmt::Expr(_src, a) => {
                 if last {
-                    self.emit("return ");
+                    self.emit("return ")?;
                 }
-                self.expr(a);
-                self.emit(";\n");
+                self.expr(a)?;
+                self.emit(";\n")?;
             }
             Stmt::For(_src, item, collection, body) => {
-                self.emit("for (");
-                self.emit(item);
-                self.emit(" of ");
-                self.expr(collection);
-                self.emit(") {\n");
-                self.block(body, false);
-                self.emit("}\n");
+                self.emit("for (")?;
+                self.emit(item)?;
+                self.emit(" of ")?;
+                self.expr(collection)?;
+                self.emit(") {\n")?;
+                self.block(body, false)?;
+                self.emit("}\n")?;
             }
             Stmt::For2(_src, idx, item, collection, body) => {
-                self.emit("for ([");
-                self.emit(idx);
-                self.emit(",");
-                self.emit(item);
-                self.emit("] of ");
-                self.expr(collection);
-                self.emit(".entries()) {\n");
-                self.block(body, false);
-                self.emit("}\n");
+                self.emit("for ([")?;
+                self.emit(idx)?;
+                self.emit(",")?;
+                self.emit(item)?;
+                self.emit("] of ")?;
+                self.expr(collection)?;
+                self.emit(".entries()) {\n")?;
+                self.block(body, false)?;
+                self.emit("}\n")?;
             }
             Stmt::Func(_src, name, params, outers, body) => {
-                self.emit("function ");
-                self.emit(name);
-                self.emit("(");
-                self.emit(&params.join(","));
-                self.emit(") {\n");
+                self.emit("function ")?;
+                self.emit(name)?;
+                self.emit("(")?;
+                self.emit(&params.join(","))?;
+                self.emit(") {\n")?;
 
                 // TODO: outers
-                let mut compiler = Compiler::new(self.out);
-                compiler.compile(params.clone(), outers.clone(), body);
-                self.emit("return null\n");
+                let mut compiler = Compiler::new(self.out, self.map);
+                compiler.compile(params.clone(), outers.clone(), body)?;
+                self.emit("return null\n")?;
 
-                self.emit("}\n");
+                self.emit("}\n")?;
             }
             Stmt::Return(_src, a) => {
-                self.emit("return ");
-                self.expr(a);
-                self.emit(";\n");
+                self.emit("return ")?;
+                self.expr(a)?;
+                self.emit(";\n")?;
             }
             Stmt::Throw(_src, a) => {
-                self.emit("throw ");
-                self.expr(a);
-                self.emit(";\n");
+                self.emit("throw ")?;
+                self.expr(a)?;
+                self.emit(";\n")?;
             }
             Stmt::Case(_src, subject, cases) => {
-                self.emit("switch (");
-                self.expr(subject);
-                self.emit(") {\n");
+                // A plain JS switch can only compare by ===, so real
+                // patterns (binding, wildcards, list destructuring, guards)
+                // are compiled to a fallthrough-free if/else-if chain
+                // instead, with `_subject` evaluated once up front
+                self.emit("{\nlet _subject=")?;
+                self.expr(subject)?;
+                self.emit(";\ndo {\n")?;
                 for (patterns, body) in cases {
-                    for pattern in patterns {
-                        self.emit("case ");
-                        self.expr(pattern);
-                        self.emit(":\n");
-                    }
                     if patterns.is_empty() {
-                        self.emit("default:\n");
+                        self.block(body, last)?;
+                        self.emit("break;\n")?;
+                        continue;
+                    }
+                    self.emit("if (")?;
+                    for (i, pattern) in patterns.iter().enumerate() {
+                        if 0 < i {
+                            self.emit("||")?;
+                        }
+                        self.emit("(")?;
+                        self.pattern_match("_subject", pattern)?;
+                        self.emit(")")?;
                     }
-                    self.block(body, last);
-                    self.emit("break;\n");
+                    self.emit(") {\n")?;
+                    self.block(body, last)?;
+                    self.emit("break;\n")?;
+                    self.emit("}\n")?;
                 }
-                self.emit("}\n");
+                self.emit("} while (false);\n")?;
+                self.emit("}\n")?;
             }
         }
+        Ok(())
     }
 
-    fn block(&mut self, v: &[Stmt], last: bool) {
+    fn block(&mut self, v: &[Stmt], last: bool) -> Result<(), Error> {
         for (i, a) in v.iter().enumerate() {
-            self.stmt(a, last && i == v.len() - 1);
+            self.stmt(a, last && i == v.len() - 1)?;
         }
+        Ok(())
     }
 
-    fn compile(&mut self, params: Vec<String>, outers: HashSet<String>, body: &Vec<Stmt>) {
+    fn compile(
+        &mut self,
+        params: Vec<String>,
+        outers: HashSet<String>,
+        body: &Vec<Stmt>,
+    ) -> Result<(), Error> {
         // Normalize parameters
         for a in &params {
-            self.emit("if (");
-            self.emit(a);
-            self.emit("=== undefined)");
-            self.emit(a);
-            self.emit("= null;\n");
+            self.emit("if (")?;
+            self.emit(a)?;
+            self.emit("=== undefined)")?;
+            self.emit(a)?;
+            self.emit("= null;\n")?;
         }
 
         // Declare variables
         self.decl_block(body);
         for a in self.assigned.clone() {
             if !(params.contains(&a) || outers.contains(&a)) {
-                self.emit("var ");
-                self.emit(&a);
-                self.emit("= null;\n");
+                self.emit("var ")?;
+                self.emit(&a)?;
+                self.emit("= null;\n")?;
             }
         }
 
         // Generate code
-        self.block(body, true);
+        self.block(body, true)
     }
 }
 
-pub fn compile(ast: &Vec<Stmt>, file: &str) {
-    let mut out = match File::create(file) {
-        Ok(a) => a,
-        Err(e) => {
-            eprintln!("{}: {}", file, e);
-            process::exit(1);
-        }
-    };
-    emit(&mut out, PREFIX_JS_BYTES);
-    let mut compiler = Compiler::new(&mut out);
-    compiler.compile(Vec::<String>::new(), HashSet::<String>::new(), ast)
+// Compiles `ast` (parsed from `source_file`) to JavaScript in `output_file`,
+// alongside a `<output_file>.map` Source Map v3 file that lets a JS
+// debugger or stack trace point back at the original `.va` source.
+pub fn compile(ast: &Vec<Stmt>, source_file: &str, output_file: &str) -> Result<(), Error> {
+    let mut out = File::create(output_file)?;
+    let mut map = SourceMap::new();
+    emit(&mut out, &mut map, PREFIX_JS_BYTES)?;
+    let mut compiler = Compiler::new(&mut out, &mut map);
+    compiler.compile(Vec::<String>::new(), HashSet::<String>::new(), ast)?;
+
+    let map_file = format!("{}.map", output_file);
+    let map_file_name = Path::new(&map_file)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or(&map_file)
+        .to_string();
+    emit(
+        &mut out,
+        &mut map,
+        format!("//# sourceMappingURL={}\n", map_file_name).as_bytes(),
+    )?;
+
+    let mut map_out = File::create(&map_file)?;
+    map_out.write_all(map.to_json(source_file).as_bytes())?;
+    Ok(())
 }
@@ -18,23 +18,43 @@ impl Env {
         }
     }
 
-    pub fn get(&self, level: usize, k: usize) -> Val {
+    // Returns an error instead of panicking when `level` reaches past the outermost
+    // scope or `k` is out of range for it, so a compiler bug surfaces as a runtime
+    // error the caller can report rather than crashing the whole process.
+    pub fn get(&self, level: usize, k: usize) -> Result<Val, String> {
         if level == 0 {
-            self.v[k].clone()
+            match self.v.get(k) {
+                Some(a) => Ok(a.clone()),
+                None => Err(format!("Slot {} out of range", k)),
+            }
         } else {
-            self.outer.as_ref().unwrap().borrow().get(level - 1, k)
+            match &self.outer {
+                Some(outer) => outer.borrow().get(level - 1, k),
+                None => Err(format!("Scope level {} out of range", level)),
+            }
         }
     }
 
-    pub fn set(&mut self, level: usize, k: usize, a: Val) {
+    // Pretty-prints this scope's own bindings (not its outer chain), for debugging
+    pub fn inspect(&self) -> String {
+        let slots: Vec<String> = self.v.iter().map(Val::inspect).collect();
+        format!("[{}]", slots.join(", "))
+    }
+
+    pub fn set(&mut self, level: usize, k: usize, a: Val) -> Result<(), String> {
         if level == 0 {
-            self.v[k] = a;
+            match self.v.get_mut(k) {
+                Some(slot) => {
+                    *slot = a;
+                    Ok(())
+                }
+                None => Err(format!("Slot {} out of range", k)),
+            }
         } else {
-            self.outer
-                .as_mut()
-                .unwrap()
-                .borrow_mut()
-                .set(level - 1, k, a);
+            match &self.outer {
+                Some(outer) => outer.borrow_mut().set(level - 1, k, a),
+                None => Err(format!("Scope level {} out of range", level)),
+            }
         }
     }
 }
@@ -1,9 +1,18 @@
+use crate::loader::{Loader, SourceId};
+use std::fmt;
+use std::io;
+
 /// An error that occurred during parsing or processing.
 ///
-/// Contains the position where the error occurred (`caret`) and a descriptive message (`msg`).
+/// Contains the source it occurred in (`source`), the position within that
+/// source (`caret`), and a descriptive message (`msg`). Carrying a
+/// `SourceId` rather than a bare offset means an error raised while parsing
+/// one file in a multi-file program still knows which file it came from.
 #[derive(Debug)]
 pub struct VError {
-    /// The character position where the error occurred in the input.
+    /// The source the error occurred in, as loaded by a `Loader`.
+    pub source: SourceId,
+    /// The character position where the error occurred within that source.
     pub caret: usize,
     /// A human-readable error message describing the problem.
     pub msg: String,
@@ -14,8 +23,7 @@ impl VError {
     ///
     /// # Arguments
     ///
-    /// * `file` - The name of the file where the error occurred
-    /// * `text` - The input text being processed when the error occurred
+    /// * `loader` - The `Loader` that owns the source text for `self.source`
     ///
     /// # Returns
     ///
@@ -24,8 +32,9 @@ impl VError {
     /// - The line of text containing the error
     /// - A caret (^) pointing to the exact position of the error
     /// - The error message
-    pub fn format_error(&self, file: &str, text: &str) -> String {
-        let chars: Vec<char> = text.chars().collect();
+    pub fn format_error(&self, loader: &Loader) -> String {
+        let file = loader.path(self.source);
+        let chars: Vec<char> = loader.text(self.source).chars().collect();
 
         // Calculate line number by counting newlines up to caret position
         let line_number = chars[..self.caret].iter().filter(|&&c| c == '\n').count() + 1;
@@ -56,3 +65,41 @@ impl VError {
         result
     }
 }
+
+/// A single error type covering every fallible operation in the compiler
+/// and runtime, so the crate can be driven as a library: callers get a
+/// `Result` back to catch and render themselves, instead of the crate
+/// calling `process::exit` out from under the host process.
+#[derive(Debug)]
+pub enum Error {
+    /// Failure writing or reading a file (e.g. the generated `.js` or its
+    /// source map).
+    Io(io::Error),
+    /// A parse-time diagnostic.
+    Parse(VError),
+    /// A runtime diagnostic raised while executing a program.
+    Runtime(VError),
+    /// Process output or input was not valid UTF-8.
+    NonUtf8,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            // `VError::format_error` needs a `Loader` to resolve the source
+            // text behind `e.source`, which `Display` has no way to supply;
+            // callers that have the `Loader` in hand should call
+            // `VError::format_error` on the inner error directly for the
+            // full caret-annotated rendering.
+            Error::Parse(e) | Error::Runtime(e) => write!(f, "{}", e.msg),
+            Error::NonUtf8 => write!(f, "input is not valid UTF-8"),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
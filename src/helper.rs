@@ -0,0 +1,202 @@
+// Support for the interactive REPL binary (see src/bin/repl.rs): a
+// rustyline `Helper` that knows enough about Verbena's surface syntax to
+// validate multi-line input, highlight it, and complete identifiers.
+use rustyline::completion::{Completer, Pair};
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::{ValidationContext, ValidationResult, Validator};
+use rustyline::{Context, Helper};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// Statement keywords that open a block requiring a matching `end`. `dowhile`
+// is deliberately excluded: it is closed by a trailing `while <cond>`, not
+// `end`.
+const BLOCK_OPENERS: &[&str] = &["if", "while", "for", "fn", "try", "case"];
+
+fn is_id_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_' || c == '$'
+}
+
+fn is_id_part(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+// Splits `line` into identifier-like words, skipping over string literals
+// and comments so a keyword spelled inside one doesn't confuse the block
+// counter or the highlighter.
+// Byte ranges of identifier-like words in `line`, skipping over string
+// literals and `#` comments so a keyword spelled inside one doesn't confuse
+// the block counter or the highlighter. Relies on every Verbena keyword
+// being ASCII, so byte and char offsets coincide.
+fn word_spans(line: &str) -> Vec<(usize, usize)> {
+    let mut r = Vec::new();
+    let bytes = line.as_bytes();
+    let mut i = 0;
+    let mut in_string = false;
+    while i < bytes.len() {
+        let c = bytes[i] as char;
+        if in_string {
+            if c == '\\' {
+                i += 2;
+                continue;
+            }
+            if c == '"' {
+                in_string = false;
+            }
+            i += 1;
+            continue;
+        }
+        if c == '"' {
+            in_string = true;
+            i += 1;
+            continue;
+        }
+        if c == '#' {
+            break;
+        }
+        if is_id_start(c) {
+            let start = i;
+            i += 1;
+            while i < bytes.len() && is_id_part(bytes[i] as char) {
+                i += 1;
+            }
+            r.push((start, i));
+            continue;
+        }
+        i += 1;
+    }
+    r
+}
+
+fn words(line: &str) -> impl Iterator<Item = &str> {
+    word_spans(line).into_iter().map(move |(a, b)| &line[a..b])
+}
+
+// The number of unmatched block openers `line` adds (positive) or closes
+// (negative) to a multi-line input's nesting depth.
+fn block_delta(line: &str) -> i32 {
+    let mut delta = 0;
+    for w in words(line) {
+        if BLOCK_OPENERS.contains(&w) {
+            delta += 1;
+        } else if w == "end" {
+            delta -= 1;
+        }
+    }
+    delta
+}
+
+pub struct ReplHelper {
+    // Refreshed by the REPL loop after each evaluation so completion sees
+    // newly `Dim`'d globals and the current set of builtins
+    pub names: Rc<RefCell<Vec<String>>>,
+}
+
+impl ReplHelper {
+    pub fn new(names: Rc<RefCell<Vec<String>>>) -> Self {
+        ReplHelper { names }
+    }
+}
+
+impl Validator for ReplHelper {
+    fn validate(&self, ctx: &mut ValidationContext) -> rustyline::Result<ValidationResult> {
+        let depth: i32 = ctx.input().lines().map(block_delta).sum();
+        if depth > 0 {
+            Ok(ValidationResult::Incomplete)
+        } else {
+            Ok(ValidationResult::Valid(None))
+        }
+    }
+}
+
+impl Highlighter for ReplHelper {
+    fn highlight<'l>(&self, line: &'l str, _pos: usize) -> Cow<'l, str> {
+        let mut out = String::with_capacity(line.len());
+        let mut last = 0;
+        let chars: Vec<char> = line.chars().collect();
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            if c == '"' {
+                let start = i;
+                i += 1;
+                while i < chars.len() && chars[i] != '"' {
+                    if chars[i] == '\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                i = (i + 1).min(chars.len());
+                out.push_str(&line[last..start]);
+                out.push_str("\x1b[32m"); // green
+                out.push_str(&line[start..i]);
+                out.push_str("\x1b[0m");
+                last = i;
+                continue;
+            }
+            if c.is_ascii_digit() {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                out.push_str(&line[last..start]);
+                out.push_str("\x1b[36m"); // cyan
+                out.push_str(&line[start..i]);
+                out.push_str("\x1b[0m");
+                last = i;
+                continue;
+            }
+            if is_id_start(c) {
+                let start = i;
+                i += 1;
+                while i < chars.len() && is_id_part(chars[i]) {
+                    i += 1;
+                }
+                let w = &line[start..i];
+                if BLOCK_OPENERS.contains(&w) || w == "end" || w == "elif" || w == "else" || w == "catch" || w == "return" || w == "throw" || w == "import" || w == "outer" || w == "typeof" || w == "assert" || w == "dowhile" {
+                    out.push_str(&line[last..start]);
+                    out.push_str("\x1b[35m"); // magenta
+                    out.push_str(w);
+                    out.push_str("\x1b[0m");
+                    last = i;
+                }
+                continue;
+            }
+            i += 1;
+        }
+        out.push_str(&line[last..]);
+        Cow::Owned(out)
+    }
+
+    fn highlight_char(&self, _line: &str, _pos: usize, _forced: bool) -> bool {
+        true
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Completer for ReplHelper {
+    type Candidate = Pair;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let prefix_start = line[..pos].rfind(|c: char| !is_id_part(c)).map(|i| i + 1).unwrap_or(0);
+        let prefix = &line[prefix_start..pos];
+        let candidates = self
+            .names
+            .borrow()
+            .iter()
+            .filter(|n| n.starts_with(prefix))
+            .map(|n| Pair {
+                display: n.clone(),
+                replacement: n.clone(),
+            })
+            .collect();
+        Ok((prefix_start, candidates))
+    }
+}
+
+impl Helper for ReplHelper {}
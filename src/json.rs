@@ -0,0 +1,345 @@
+// A standards-compliant (RFC 8259) JSON codec for Val: `to_json` renders
+// any value built from the JSON data model (null, booleans, numbers,
+// strings, arrays, and Object maps) as compact JSON text; `from_json` is
+// its inverse, building nested List/Object Vals from parsed JSON text.
+// Unlike the netstring format in `serialize`, this is meant to interop
+// with the wider world, so it follows the JSON grammar exactly rather
+// than a Verbena-specific encoding.
+use crate::list::*;
+use crate::object::Object;
+use crate::val::*;
+use num_bigint::BigInt;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::str::FromStr;
+
+pub fn to_json(v: &Val) -> Result<String, String> {
+    let mut out = String::new();
+    write_json(v, &mut out)?;
+    Ok(out)
+}
+
+fn write_json(v: &Val, out: &mut String) -> Result<(), String> {
+    match v {
+        Val::Null => out.push_str("null"),
+        Val::True => out.push_str("true"),
+        Val::False => out.push_str("false"),
+        Val::Int(a) => out.push_str(&a.to_string()),
+        Val::Num(a) => {
+            if !a.is_finite() {
+                return Err("Cannot represent a non-finite number as JSON".to_string());
+            }
+            let s = a.to_string();
+            out.push_str(&s);
+            // f64's Display omits the decimal point for whole numbers (e.g.
+            // -2000.0 -> "-2000"), which JSON would then read back as an
+            // integer; append ".0" so Num round-trips as Num
+            if !s.contains('.') && !s.contains('e') && !s.contains('E') {
+                out.push_str(".0");
+            }
+        }
+        Val::Str(s) => write_json_string(s, out),
+        Val::List(a) => {
+            out.push('[');
+            for (i, item) in a.borrow().v.iter().enumerate() {
+                if 0 < i {
+                    out.push(',');
+                }
+                write_json(item, out)?;
+            }
+            out.push(']');
+        }
+        Val::Object(a) => {
+            out.push('{');
+            for (i, (key, value)) in a.borrow().iter().enumerate() {
+                if 0 < i {
+                    out.push(',');
+                }
+                write_json_string(key, out);
+                out.push(':');
+                write_json(value, out)?;
+            }
+            out.push('}');
+        }
+        _ => return Err("Cannot represent this type as JSON".to_string()),
+    }
+    Ok(())
+}
+
+// Escapes per RFC 8259: '"', '\\', and control characters; everything
+// else passes through as-is, since JSON strings are UTF-8
+fn write_json_string(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+// A minimal recursive-descent JSON parser over the input's raw bytes,
+// since a well-formed JSON document is pure ASCII outside of string
+// contents and string contents are copied out (and re-validated as
+// UTF-8) in whole runs rather than decoded one code point at a time
+struct JsonParser<'a> {
+    text: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> JsonParser<'a> {
+    fn skip_ws(&mut self) {
+        while matches!(self.peek(), Some(b) if b.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.get(self.pos).copied()
+    }
+
+    fn expect(&mut self, b: u8) -> Result<(), String> {
+        if self.peek() == Some(b) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(format!("Expected '{}' at byte offset {}", b as char, self.pos))
+        }
+    }
+
+    fn expect_literal(&mut self, lit: &str) -> Result<(), String> {
+        let bytes = lit.as_bytes();
+        if self.text[self.pos..].starts_with(bytes) {
+            self.pos += bytes.len();
+            Ok(())
+        } else {
+            Err(format!("Expected '{}' at byte offset {}", lit, self.pos))
+        }
+    }
+
+    fn value(&mut self) -> Result<Val, String> {
+        self.skip_ws();
+        match self.peek() {
+            Some(b'{') => self.object(),
+            Some(b'[') => self.array(),
+            Some(b'"') => Ok(Val::Str(self.string()?)),
+            Some(b't') => {
+                self.expect_literal("true")?;
+                Ok(Val::True)
+            }
+            Some(b'f') => {
+                self.expect_literal("false")?;
+                Ok(Val::False)
+            }
+            Some(b'n') => {
+                self.expect_literal("null")?;
+                Ok(Val::Null)
+            }
+            Some(c) if c == b'-' || c.is_ascii_digit() => self.number(),
+            Some(c) => Err(format!(
+                "Unexpected character '{}' at byte offset {}",
+                c as char, self.pos
+            )),
+            None => Err("Unexpected end of input".to_string()),
+        }
+    }
+
+    fn object(&mut self) -> Result<Val, String> {
+        self.expect(b'{')?;
+        let mut obj = Object::new();
+        self.skip_ws();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(Val::object(obj));
+        }
+        loop {
+            self.skip_ws();
+            let key = self.string()?;
+            self.skip_ws();
+            self.expect(b':')?;
+            let value = self.value()?;
+            obj.insert(key, value);
+            self.skip_ws();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    break;
+                }
+                _ => return Err(format!("Expected ',' or '}}' at byte offset {}", self.pos)),
+            }
+        }
+        Ok(Val::object(obj))
+    }
+
+    fn array(&mut self) -> Result<Val, String> {
+        self.expect(b'[')?;
+        let mut items = Vec::<Val>::new();
+        self.skip_ws();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(Val::List(Rc::new(RefCell::new(List::from(items)))));
+        }
+        loop {
+            items.push(self.value()?);
+            self.skip_ws();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    break;
+                }
+                _ => return Err(format!("Expected ',' or ']' at byte offset {}", self.pos)),
+            }
+        }
+        Ok(Val::List(Rc::new(RefCell::new(List::from(items)))))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect(b'"')?;
+        let mut s = String::new();
+        loop {
+            match self.peek() {
+                None => return Err("Unterminated string".to_string()),
+                Some(b'"') => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(b'\\') => {
+                    self.pos += 1;
+                    match self.peek() {
+                        Some(b'"') => {
+                            s.push('"');
+                            self.pos += 1;
+                        }
+                        Some(b'\\') => {
+                            s.push('\\');
+                            self.pos += 1;
+                        }
+                        Some(b'/') => {
+                            s.push('/');
+                            self.pos += 1;
+                        }
+                        Some(b'b') => {
+                            s.push('\u{8}');
+                            self.pos += 1;
+                        }
+                        Some(b'f') => {
+                            s.push('\u{c}');
+                            self.pos += 1;
+                        }
+                        Some(b'n') => {
+                            s.push('\n');
+                            self.pos += 1;
+                        }
+                        Some(b'r') => {
+                            s.push('\r');
+                            self.pos += 1;
+                        }
+                        Some(b't') => {
+                            s.push('\t');
+                            self.pos += 1;
+                        }
+                        Some(b'u') => {
+                            self.pos += 1;
+                            let hi = self.hex4()?;
+                            let code = if (0xd800..=0xdbff).contains(&hi) {
+                                self.expect(b'\\')?;
+                                self.expect(b'u')?;
+                                let lo = self.hex4()?;
+                                if !(0xdc00..=0xdfff).contains(&lo) {
+                                    return Err("Invalid UTF-16 surrogate pair".to_string());
+                                }
+                                0x10000 + ((hi - 0xd800) << 10) + (lo - 0xdc00)
+                            } else {
+                                hi
+                            };
+                            let c = char::from_u32(code).ok_or("Invalid unicode escape")?;
+                            s.push(c);
+                        }
+                        _ => return Err("Invalid escape sequence".to_string()),
+                    }
+                }
+                Some(b) if b < 0x20 => return Err("Control character in string".to_string()),
+                Some(_) => {
+                    let start = self.pos;
+                    while matches!(self.peek(), Some(b) if b != b'"' && b != b'\\' && b >= 0x20) {
+                        self.pos += 1;
+                    }
+                    let chunk = std::str::from_utf8(&self.text[start..self.pos])
+                        .map_err(|e| e.to_string())?;
+                    s.push_str(chunk);
+                }
+            }
+        }
+        Ok(s)
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        if self.pos + 4 > self.text.len() {
+            return Err("Truncated unicode escape".to_string());
+        }
+        let hex = std::str::from_utf8(&self.text[self.pos..self.pos + 4])
+            .map_err(|e| e.to_string())?;
+        let code = u32::from_str_radix(hex, 16).map_err(|_| "Invalid unicode escape".to_string())?;
+        self.pos += 4;
+        Ok(code)
+    }
+
+    fn number(&mut self) -> Result<Val, String> {
+        let start = self.pos;
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        while matches!(self.peek(), Some(b) if b.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        let mut is_float = false;
+        if self.peek() == Some(b'.') {
+            is_float = true;
+            self.pos += 1;
+            while matches!(self.peek(), Some(b) if b.is_ascii_digit()) {
+                self.pos += 1;
+            }
+        }
+        if matches!(self.peek(), Some(b'e') | Some(b'E')) {
+            is_float = true;
+            self.pos += 1;
+            if matches!(self.peek(), Some(b'+') | Some(b'-')) {
+                self.pos += 1;
+            }
+            while matches!(self.peek(), Some(b) if b.is_ascii_digit()) {
+                self.pos += 1;
+            }
+        }
+        let s = std::str::from_utf8(&self.text[start..self.pos]).map_err(|e| e.to_string())?;
+        if is_float {
+            let a: f64 = s.parse().map_err(|_| "Invalid number".to_string())?;
+            Ok(Val::Num(a))
+        } else {
+            let a = BigInt::from_str(s).map_err(|_| "Invalid number".to_string())?;
+            Ok(Val::Int(a))
+        }
+    }
+}
+
+pub fn from_json(s: &str) -> Result<Val, String> {
+    let mut p = JsonParser {
+        text: s.as_bytes(),
+        pos: 0,
+    };
+    let v = p.value()?;
+    p.skip_ws();
+    if p.pos != p.text.len() {
+        return Err("Trailing data after JSON value".to_string());
+    }
+    Ok(v)
+}
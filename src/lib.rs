@@ -3,11 +3,18 @@ pub mod code;
 pub mod compile_error;
 pub mod compiler;
 pub mod env;
+pub mod error;
 pub mod error_context;
 pub mod func;
+pub mod helper;
+pub mod json;
 pub mod list;
+pub mod loader;
 pub mod object;
+pub mod optimize;
 pub mod parser;
+pub mod program;
+pub mod serialize;
 pub mod stdlib;
 pub mod val;
 pub mod vm;
@@ -17,11 +24,21 @@ pub use code::*;
 pub use compile_error::*;
 pub use compiler::*;
 pub use env::*;
+pub use error::*;
 pub use error_context::*;
 pub use func::*;
+pub use helper::*;
+pub use json::*;
 pub use list::*;
+pub use loader::*;
 pub use object::*;
+pub use optimize::*;
 pub use parser::*;
+// Only Program itself is re-exported here, not program::*: program::Inst
+// would collide with code::Inst, an unrelated older bytecode format that's
+// also glob-exported from this crate root.
+pub use program::Program;
+pub use serialize::*;
 pub use stdlib::*;
 pub use val::*;
 pub use vm::*;
@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+use std::io;
+
+/// Identifies one source file loaded into a `Loader`'s arena.
+///
+/// Stable for the lifetime of the `Loader`: loading more sources never
+/// invalidates ids handed out earlier, so a `VError` can hold one of these
+/// instead of a borrowed `&str` it would otherwise have to smuggle through
+/// every parser and call site.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SourceId(usize);
+
+/// Owns the text of every source file pulled into a compilation.
+///
+/// A single top-level file loads just one source, but this is also the
+/// precondition for an `import`/module system: when one `.va` file pulls in
+/// another, each gets its own `SourceId`, and an error raised while parsing
+/// the imported file still points at the right line in the right file.
+#[derive(Debug, Default)]
+pub struct Loader {
+    paths: Vec<String>,
+    texts: Vec<String>,
+    by_path: HashMap<String, SourceId>,
+}
+
+impl Loader {
+    pub fn new() -> Self {
+        Loader::default()
+    }
+
+    /// Reads `path` from disk and returns its `SourceId`, reusing the
+    /// existing id if the same path was already loaded (e.g. two modules
+    /// importing the same dependency).
+    pub fn load(&mut self, path: &str) -> io::Result<SourceId> {
+        if let Some(&id) = self.by_path.get(path) {
+            return Ok(id);
+        }
+        let text = std::fs::read_to_string(path)?;
+        Ok(self.insert(path, text))
+    }
+
+    /// Registers source text that didn't come from disk (an already-read
+    /// top-level file, REPL input, ...) and returns its `SourceId`.
+    pub fn insert(&mut self, path: &str, text: String) -> SourceId {
+        if let Some(&id) = self.by_path.get(path) {
+            self.texts[id.0] = text;
+            return id;
+        }
+        let id = SourceId(self.texts.len());
+        self.paths.push(path.to_string());
+        self.texts.push(text);
+        self.by_path.insert(path.to_string(), id);
+        id
+    }
+
+    pub fn path(&self, id: SourceId) -> &str {
+        &self.paths[id.0]
+    }
+
+    pub fn text(&self, id: SourceId) -> &str {
+        &self.texts[id.0]
+    }
+}
@@ -1,9 +1,12 @@
 mod ast;
 mod compiler;
+mod error;
+mod loader;
 mod parser;
 use clap::{Arg, Command};
 use compiler::*;
 use parser::*;
+use std::process;
 
 fn main() {
     let matches = Command::new("Verbena")
@@ -20,6 +23,9 @@ fn main() {
         .get_matches();
     let file = matches.get_one::<String>("file").unwrap();
     let output = matches.get_one::<String>("output").unwrap();
-    let ast = parse(file);
-    compile(&ast, output);
+    let ast = parse_or_exit(file);
+    if let Err(e) = compile(&ast, file, output) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
 }
@@ -1,10 +1,13 @@
 use crate::val::*;
-use std::collections::HashMap;
+use indexmap::IndexMap;
 use std::fmt;
 
 #[derive(Debug, Clone)]
 pub struct Object {
-    m: HashMap<String, Val>,
+    // IndexMap instead of HashMap so key order follows insertion order,
+    // giving Display and serialization (to_json) deterministic,
+    // reproducible output instead of varying from run to run
+    m: IndexMap<String, Val>,
 }
 
 impl Default for Object {
@@ -15,7 +18,7 @@ impl Default for Object {
 
 impl Object {
     pub fn new() -> Self {
-        Object { m: HashMap::new() }
+        Object { m: IndexMap::new() }
     }
 
     pub fn is_empty(&self) -> bool {
@@ -25,6 +28,26 @@ impl Object {
     pub fn len(&self) -> usize {
         self.m.len()
     }
+
+    pub fn get(&self, key: &str) -> Option<&Val> {
+        self.m.get(key)
+    }
+
+    pub fn insert(&mut self, key: String, value: Val) -> Option<Val> {
+        self.m.insert(key, value)
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.m.keys()
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Val> {
+        self.m.values()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Val)> {
+        self.m.iter()
+    }
 }
 
 impl PartialEq for Object {
@@ -42,7 +65,7 @@ impl fmt::Display for Object {
 
         write!(f, "{{")?;
         let mut first = true;
-        for (key, value) in &self.m {
+        for (key, value) in self.iter() {
             if !first {
                 write!(f, ", ")?;
             }
@@ -0,0 +1,211 @@
+// Peephole optimization over a Program's instruction stream, run when
+// requested via an opt-level flag (mirroring -O/opt-level in mainstream
+// compilers): level 0 leaves the program untouched; level 1 and up
+// repeatedly applies constant folding, unreachable-branch elimination,
+// jump threading, and dead-code elimination until a full pass makes no
+// further change, since each transform can expose opportunities for the
+// others.
+//
+// `Br`/`BrTrue`/`BrFalse`/`DupBrTrue`/`DupBrFalse`/`PushHandler` targets
+// are absolute indices into `code`, so any pass that inserts or removes
+// instructions goes through `apply_rewrites`, which rebuilds the code
+// vector from a set of (possibly length-changing) range replacements and
+// retargets every branch to wherever its old destination ended up.
+use crate::program::{branch_target, set_branch_target, Inst, Program};
+use crate::val::{eq_loose, le_loose, lt_loose, Val};
+use crate::vm::{add, bit_and, bit_or, bit_xor, fdiv, idiv, mod_, mul, pow, shl, shr, sub};
+use std::collections::HashSet;
+
+impl Program {
+    pub fn optimize(self, opt_level: u32) -> Program {
+        if opt_level == 0 {
+            return self;
+        }
+        let mut code = self.code;
+        // A pathological chain of jumps threading through each other could
+        // in principle cycle forever; bound the number of passes so
+        // optimization always terminates, real programs converge in far fewer.
+        for _ in 0..code.len() + 16 {
+            let mut changed = false;
+
+            let rewrites = fold_constants(&code);
+            if !rewrites.is_empty() {
+                code = apply_rewrites(code, rewrites);
+                changed = true;
+            }
+
+            let rewrites = eliminate_unreachable_branches(&code);
+            if !rewrites.is_empty() {
+                code = apply_rewrites(code, rewrites);
+                changed = true;
+            }
+
+            if thread_jumps(&mut code) {
+                changed = true;
+            }
+
+            let rewrites = eliminate_dead_code(&code);
+            if !rewrites.is_empty() {
+                code = apply_rewrites(code, rewrites);
+                changed = true;
+            }
+
+            if !changed {
+                break;
+            }
+        }
+        Program { code }
+    }
+}
+
+// Replaces each (start, end) range of `code` with its paired replacement,
+// then rewrites every surviving branch target to point at wherever its
+// old destination landed (the first instruction of its replacement, or
+// the next surviving instruction if the range was deleted outright).
+fn apply_rewrites(code: Vec<Inst>, mut rewrites: Vec<(usize, usize, Vec<Inst>)>) -> Vec<Inst> {
+    rewrites.sort_by_key(|r| r.0);
+    let old_len = code.len();
+    let mut new_code = Vec::with_capacity(old_len);
+    let mut old_to_new = vec![0usize; old_len + 1];
+    let mut ri = 0;
+    let mut iter = code.into_iter().enumerate();
+    while let Some((i, inst)) = iter.next() {
+        if ri < rewrites.len() && rewrites[ri].0 == i {
+            let (start, end, replacement) = std::mem::take(&mut rewrites[ri]);
+            let new_start = new_code.len();
+            new_code.extend(replacement);
+            old_to_new[start..end].fill(new_start);
+            // `inst` (index `start`) is already consumed; skip the rest of
+            // the replaced range, which the iterator hasn't reached yet
+            for _ in start + 1..end {
+                iter.next();
+            }
+            ri += 1;
+        } else {
+            old_to_new[i] = new_code.len();
+            new_code.push(inst);
+        }
+    }
+    old_to_new[old_len] = new_code.len();
+    for inst in &mut new_code {
+        if let Some(t) = branch_target(inst) {
+            set_branch_target(inst, old_to_new[t]);
+        }
+    }
+    new_code
+}
+
+// Evaluates a pure binary op over two already-known operands the same way
+// the VM itself would, by replaying it through the VM's own stack-based
+// implementation; returns None if the op would error at runtime (e.g.
+// division by zero), leaving the original instructions in place so the
+// error still surfaces when the code actually runs.
+fn fold_binop(inst: &Inst, a: &Val, b: &Val) -> Option<Val> {
+    let mut stack = vec![a.clone(), b.clone()];
+    match inst {
+        Inst::Add => add(&mut stack).ok()?,
+        Inst::Sub(_) => sub(&mut stack).ok()?,
+        Inst::Mul(_) => mul(&mut stack).ok()?,
+        Inst::IDiv(_) => idiv(&mut stack).ok()?,
+        Inst::FDiv(_) => fdiv(&mut stack).ok()?,
+        Inst::Mod(_) => mod_(&mut stack).ok()?,
+        Inst::Shl(_) => shl(&mut stack).ok()?,
+        Inst::Shr(_) => shr(&mut stack).ok()?,
+        Inst::BitAnd(_) => bit_and(&mut stack).ok()?,
+        Inst::BitOr(_) => bit_or(&mut stack).ok()?,
+        Inst::BitXor(_) => bit_xor(&mut stack).ok()?,
+        Inst::Pow(_) => pow(&mut stack).ok()?,
+        Inst::Eq => return Some(Val::from_bool(eq_loose(a, b))),
+        Inst::Ne => return Some(Val::from_bool(!eq_loose(a, b))),
+        Inst::Lt => return Some(Val::from_bool(lt_loose(a, b))),
+        Inst::Gt => return Some(Val::from_bool(lt_loose(b, a))),
+        Inst::Le => return Some(Val::from_bool(le_loose(a, b))),
+        Inst::Ge => return Some(Val::from_bool(le_loose(b, a))),
+        _ => return None,
+    };
+    stack.pop()
+}
+
+// Const, Const, <pure binary op> -> Const(result)
+fn fold_constants(code: &[Inst]) -> Vec<(usize, usize, Vec<Inst>)> {
+    let mut rewrites = Vec::new();
+    let mut i = 0;
+    while i + 2 < code.len() {
+        if let (Inst::Const(a), Inst::Const(b)) = (&code[i], &code[i + 1]) {
+            if let Some(r) = fold_binop(&code[i + 2], a, b) {
+                rewrites.push((i, i + 3, vec![Inst::Const(r)]));
+                i += 3;
+                continue;
+            }
+        }
+        i += 1;
+    }
+    rewrites
+}
+
+// Const(true), BrFalse and Const(false), BrTrue can never take their
+// branch: the condition is pushed only to be popped and found not to
+// match, so both instructions can be dropped and execution simply falls
+// through.
+fn eliminate_unreachable_branches(code: &[Inst]) -> Vec<(usize, usize, Vec<Inst>)> {
+    let mut rewrites = Vec::new();
+    let mut i = 0;
+    while i + 1 < code.len() {
+        let drop = matches!(
+            (&code[i], &code[i + 1]),
+            (Inst::Const(Val::True), Inst::BrFalse(_)) | (Inst::Const(Val::False), Inst::BrTrue(_))
+        );
+        if drop {
+            rewrites.push((i, i + 2, Vec::new()));
+            i += 2;
+            continue;
+        }
+        i += 1;
+    }
+    rewrites
+}
+
+// If a branch's target is itself an unconditional Br, retarget straight to
+// the final destination instead of bouncing through an intermediate jump.
+fn thread_jumps(code: &mut [Inst]) -> bool {
+    let mut changed = false;
+    for i in 0..code.len() {
+        if let Some(t) = branch_target(&code[i]) {
+            if let Some(&Inst::Br(t2)) = code.get(t) {
+                if t2 != t {
+                    set_branch_target(&mut code[i], t2);
+                    changed = true;
+                }
+            }
+        }
+    }
+    changed
+}
+
+// Drops any run of instructions that immediately follows an unconditional
+// control transfer (Br/Return/Exit/Throw) and isn't itself the target of
+// some other branch -- code nothing can reach. The branch-target set is
+// collected up front so DCE never deletes a landing site out from under a
+// branch that's still going to jump there.
+fn eliminate_dead_code(code: &[Inst]) -> Vec<(usize, usize, Vec<Inst>)> {
+    let targets: HashSet<usize> = code.iter().filter_map(branch_target).collect();
+    let mut rewrites = Vec::new();
+    let mut i = 0;
+    while i < code.len() {
+        let terminates = matches!(code[i], Inst::Br(_) | Inst::Return | Inst::Exit | Inst::Throw);
+        if terminates {
+            let start = i + 1;
+            let mut end = start;
+            while end < code.len() && !targets.contains(&end) {
+                end += 1;
+            }
+            if end > start {
+                rewrites.push((start, end, Vec::new()));
+            }
+            i = end;
+        } else {
+            i += 1;
+        }
+    }
+    rewrites
+}
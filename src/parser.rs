@@ -1,14 +1,20 @@
 use crate::ast::*;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::fmt;
 use std::fs;
 use std::process;
 
+// Exposed (rather than kept private to the recursive-descent parser) so
+// `tokenize` can hand a spanned token stream to an embedder without the
+// embedder reimplementing the lexer
 #[derive(Clone, Hash, PartialEq, Eq, Debug)]
-enum Tok {
+pub enum Tok {
     Add,
     AddAssign,
     And,
+    Arrow,
     Assert,
     Assign,
     Atom(String),
@@ -22,11 +28,13 @@ enum Tok {
     Catch,
     Colon,
     Comma,
+    Dedent,
     Div,
     DivAssign,
     Dot,
     Dowhile,
     Elif,
+    Ellipsis,
     Else,
     End,
     Eof,
@@ -37,6 +45,7 @@ enum Tok {
     Gt,
     If,
     Import,
+    Indent,
     LBrace,
     LParen,
     LShr,
@@ -55,6 +64,7 @@ enum Tok {
     Or,
     Outer,
     Pipe,
+    PipeForward,
     Pow,
     PowAssign,
     RBrace,
@@ -72,6 +82,50 @@ enum Tok {
     While,
 }
 
+// What kind of thing the parser expected but didn't get, so a caller that
+// wants to react programmatically (an LSP, a test harness) doesn't have to
+// pattern-match on `message`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    BadLabel,
+    ExpectedNewline,
+    Lex,
+    MalformedNumber,
+    MissingEnd,
+    MissingRParen,
+    Syntax,
+    UnmatchedTerminator,
+}
+
+// Replaces the old panic-based `Parser::err`, so the parser can be embedded
+// as a library (an editor, an LSP, a test harness) instead of aborting the
+// whole process on the first problem
+#[derive(Clone, Debug)]
+pub struct ParseError {
+    pub src: Src,
+    pub kind: ParseErrorKind,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.src, self.message)
+    }
+}
+
+impl ParseError {
+    // Like `Display`, but with the offending source line and a `^` caret
+    // at the column appended underneath, for terminal-facing diagnostics.
+    // Takes `text` rather than storing it on the error itself, since
+    // recovering-mode parses can collect hundreds of these and shouldn't
+    // each carry a copy of the whole file
+    pub fn render(&self, text: &str) -> String {
+        let line = text.lines().nth(self.src.line.saturating_sub(1)).unwrap_or("");
+        let caret = " ".repeat(self.src.col.saturating_sub(1)) + "^";
+        format!("{self}\n{line}\n{caret}")
+    }
+}
+
 // The operator precedence parser uses a table of these
 #[derive(Clone)]
 struct Op {
@@ -81,6 +135,37 @@ struct Op {
     compound_assign: bool,
 }
 
+// A lightweight snapshot of the lexer's position, used to backtrack when
+// speculatively parsing a lambda's parameter list turns out to be an
+// ordinary atom or grouped expression instead. This is this grammar's
+// multi-token lookahead mechanism: rather than a fixed-size ring buffer of
+// pre-lexed tokens, a caller that needs to look arbitrarily far ahead
+// (`try_lambda_params` may cross a whole parameter list and a dedent before
+// deciding) takes a `checkpoint`, speculatively calls `lex` as many times as
+// it needs, and `restore`s if the lookahead didn't pan out
+struct Checkpoint {
+    pos: usize,
+    line: usize,
+    col: usize,
+    tok: Tok,
+    tok_src: Src,
+    prev_tok_end: usize,
+    // In offside mode, a single lex() call crossing a line transition can
+    // queue several Indent/Dedent tokens before the text cursor itself
+    // advances past them; a speculative lookahead (e.g. try_lambda_params)
+    // that lexes ahead and then backtracks has to roll this queue back too,
+    // or the queued tokens get handed out out of order once parsing resumes
+    // from the restored `pos`
+    pending: VecDeque<Tok>,
+    // `offside_transition` mutates `indent_stack` as a side effect of the
+    // same lex() call that fills `pending`; rolling back one without the
+    // other leaves them out of sync, so a lookahead that crosses a line
+    // transition and then backtracks makes the re-lex of that same
+    // transition see a stale indentation level and wrongly conclude
+    // nothing changed
+    indent_stack: Vec<usize>,
+}
+
 struct Parser {
     // There is a compile-time perfect hash package
     // but there are benchmarks showing HashMap to be faster
@@ -96,11 +181,33 @@ struct Parser {
     // Current position in the text
     pos: usize,
 
-    // Line number tracker for error reporting
+    // Line/column tracker for error reporting and spans, 1-based,
+    // column reset to 1 on '\n'
     line: usize,
+    col: usize,
 
-    // Current token
+    // Current token, and the span it was lexed from
     tok: Tok,
+    tok_src: Src,
+
+    // End offset of the token just replaced by the current one, i.e. the
+    // end of the last token actually consumed by the parser so far
+    prev_tok_end: usize,
+
+    // Error-recovery mode: instead of aborting on the first syntax error,
+    // `primary`/`postfix`/`stmt` record it here and resynchronize, so a
+    // caller (an LSP, a test harness) gets every diagnostic from one pass
+    recovering: bool,
+    errors: Vec<ParseError>,
+
+    // Offside-rule mode: opted into by a `#offside` pragma on the file's
+    // first line. `indent_stack` is the stack of indentation columns
+    // currently open (base level 0), and `pending` holds the `Indent`/
+    // `Dedent` tokens still to be handed out before the lexer resumes
+    // scanning text, since one line transition can need several of them
+    offside: bool,
+    indent_stack: Vec<usize>,
+    pending: VecDeque<Tok>,
 }
 
 fn is_id_start(c: char) -> bool {
@@ -115,6 +222,34 @@ fn substr(text: &[char], i: usize, j: usize) -> String {
     text.iter().skip(i).take(j - i).collect()
 }
 
+// Like `text[i]`, but returns a sentinel instead of panicking past the end
+// of input; used by escape-sequence validation, where a truncated `\x`/`\u`
+// at the very end of a source file is a malformed-escape error rather than
+// something that should be allowed to index out of bounds
+fn char_at(text: &[char], i: usize) -> char {
+    *text.get(i).unwrap_or(&'\0')
+}
+
+// Used to recover from a malformed escape sequence: scans forward from `i`
+// (inside a string started by `q`) to the closing quote, so the lexer
+// doesn't leave the tail of the string (which may itself contain quotes,
+// braces, etc.) lying around to be re-lexed as unrelated tokens. Returns an
+// index one past the closing quote, or `text.len()` if the string runs to
+// EOF without one.
+fn skip_to_string_end(text: &[char], mut i: usize, q: char) -> usize {
+    while i < text.len() && text[i] != q && text[i] != '\n' {
+        if text[i] == '\\' && i + 1 < text.len() {
+            i += 2;
+        } else {
+            i += 1;
+        }
+    }
+    if i < text.len() && text[i] == q {
+        i += 1;
+    }
+    i
+}
+
 impl Parser {
     fn new(file: String, text: Vec<char>) -> Self {
         // Keywords
@@ -178,6 +313,12 @@ impl Parser {
         prec -= 1;
         op(Tok::Pipe, prec, 1, "|", false);
 
+        // Left-to-right pipeline: one level above comparison, so
+        // `a |> f > 0` groups as `(a |> f) > 0` rather than piping into
+        // `f > 0`
+        prec -= 1;
+        op(Tok::PipeForward, prec, 1, "|>", false);
+
         prec -= 1;
         op(Tok::Eq, prec, 1, "===", false);
         op(Tok::Ne, prec, 1, "!==", false);
@@ -214,6 +355,19 @@ impl Parser {
 
         op(Tok::BitOrAssign, prec, 0, "|", true);
 
+        let tok_src = Src {
+            file: file.clone(),
+            line: 1,
+            col: 1,
+            start_offset: 0,
+            end_offset: 0,
+        };
+
+        // Opt-in offside-rule mode: a lone `#offside` pragma on the file's
+        // first line, same spelling as the comment syntax it rides on
+        let first_line: String = text.iter().take_while(|&&c| c != '\n').collect();
+        let offside = first_line.trim() == "#offside";
+
         Parser {
             keywords,
             ops,
@@ -221,20 +375,195 @@ impl Parser {
             text,
             pos: 0,
             line: 1,
+            col: 1,
             tok: Tok::Newline,
+            tok_src,
+            prev_tok_end: 0,
+            recovering: false,
+            errors: Vec::new(),
+            offside,
+            indent_stack: vec![0],
+            pending: VecDeque::new(),
         }
     }
 
+    // Advance `pos`/`col` together by `n` characters, none of which is a newline
+    fn advance(&mut self, n: usize) {
+        self.pos += n;
+        self.col += n;
+    }
+
+    // Advance `pos` to `new_pos`, bumping `col` by the same amount; used after
+    // lex_id/lex_num scan a run of characters with a local cursor
+    fn advance_to(&mut self, new_pos: usize) {
+        self.col += new_pos - self.pos;
+        self.pos = new_pos;
+    }
+
+    fn newline(&mut self) {
+        self.pos += 1;
+        self.line += 1;
+        self.col = 1;
+    }
+
+    // The span of the current token
     fn src(&self) -> Src {
+        self.tok_src.clone()
+    }
+
+    // The span from `start` (captured before parsing a node) to the end of
+    // the last token actually consumed for it
+    fn span(&self, start: &Src) -> Src {
         Src {
             file: self.file.clone(),
+            line: start.line,
+            col: start.col,
+            start_offset: start.start_offset,
+            end_offset: self.prev_tok_end,
+        }
+    }
+
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            pos: self.pos,
             line: self.line,
+            col: self.col,
+            tok: self.tok.clone(),
+            tok_src: self.tok_src.clone(),
+            prev_tok_end: self.prev_tok_end,
+            pending: self.pending.clone(),
+            indent_stack: self.indent_stack.clone(),
+        }
+    }
+
+    fn restore(&mut self, c: Checkpoint) {
+        self.pos = c.pos;
+        self.line = c.line;
+        self.col = c.col;
+        self.tok = c.tok;
+        self.tok_src = c.tok_src;
+        self.prev_tok_end = c.prev_tok_end;
+        self.pending = c.pending;
+        self.indent_stack = c.indent_stack;
+    }
+
+    fn err<S: Into<String>>(&self, kind: ParseErrorKind, msg: S) -> ParseError {
+        ParseError {
+            src: self.src(),
+            kind,
+            message: msg.into(),
+        }
+    }
+
+    // Like `err`, but for errors raised mid-`lex()`: at that point
+    // `self.tok_src` still names the *previous* token (the current one
+    // isn't assembled until `lex` returns successfully), so a lex error
+    // needs to be given the position it's actually at explicitly
+    fn err_at<S: Into<String>>(
+        &self,
+        line: usize,
+        col: usize,
+        start_offset: usize,
+        end_offset: usize,
+        kind: ParseErrorKind,
+        msg: S,
+    ) -> ParseError {
+        ParseError {
+            src: Src {
+                file: self.file.clone(),
+                line,
+                col,
+                start_offset,
+                end_offset,
+            },
+            kind,
+            message: msg.into(),
+        }
+    }
+
+    // Skip tokens until a safe resynchronization point: a newline, 'end',
+    // a closing bracket, or a statement-starting keyword. Bracket nesting
+    // is tracked so a stray ')'/']'/'}' inside a still-open '('/'['/'{'
+    // doesn't desync. Always consumes at least one token (via `lex`)
+    // before looping again, so a caller can never spin forever here.
+    fn synchronize(&mut self) {
+        let mut depth = 0i32;
+        loop {
+            match self.tok {
+                Tok::Eof => return,
+                Tok::LParen | Tok::LSquare | Tok::LBrace => depth += 1,
+                Tok::RParen | Tok::RSquare | Tok::RBrace => {
+                    if depth == 0 {
+                        return;
+                    }
+                    depth -= 1;
+                }
+                Tok::Newline | Tok::End | Tok::If | Tok::While | Tok::For | Tok::Func
+                | Tok::Return | Tok::Try
+                    if depth == 0 =>
+                {
+                    return;
+                }
+                _ => {}
+            }
+            // lex() can itself fail (e.g. on a malformed number); that's
+            // just another token to skip over, not a reason to stop
+            let _ = self.lex();
         }
     }
 
-    fn err<S: AsRef<str>>(&self, msg: S) -> ! {
-        eprintln!("{}: {}", self.src(), msg.as_ref());
-        process::exit(1);
+    // Records `e` and resynchronizes; only called when `self.recovering`
+    fn record_error(&mut self, e: ParseError) {
+        self.errors.push(e);
+        self.synchronize();
+    }
+
+    // Compares the indentation of the line starting at `indent_start`
+    // (0 if we've run off the end of the file) against `indent_stack`,
+    // queuing the `Indent`/`Dedent` tokens needed to reconcile them. Called
+    // once per line transition, only in offside mode
+    fn offside_transition(&mut self, indent_start: usize) -> Result<(), ParseError> {
+        let at_eof = self.pos >= self.text.len();
+        let new_indent = if at_eof {
+            0
+        } else {
+            let indent_chars = &self.text[indent_start..self.pos];
+            if indent_chars.contains(&' ') && indent_chars.contains(&'\t') {
+                // `indent_start` is only ever the first character of a line,
+                // where `newline()` has just reset `col` to 1
+                return Err(self.err_at(
+                    self.line,
+                    1,
+                    indent_start,
+                    self.pos,
+                    ParseErrorKind::Lex,
+                    "Inconsistent indentation: tabs and spaces mixed on the same line",
+                ));
+            }
+            indent_chars.len()
+        };
+
+        let top = *self.indent_stack.last().unwrap();
+        if new_indent > top {
+            self.indent_stack.push(new_indent);
+            self.pending.push_back(Tok::Indent);
+        } else if new_indent < top {
+            while *self.indent_stack.last().unwrap() > new_indent {
+                self.indent_stack.pop();
+                self.pending.push_back(Tok::Dedent);
+            }
+            if *self.indent_stack.last().unwrap() != new_indent {
+                return Err(self.err_at(
+                    self.line,
+                    1,
+                    indent_start,
+                    self.pos,
+                    ParseErrorKind::Lex,
+                    "Dedent does not match any enclosing indentation level",
+                ));
+            }
+        }
+        Ok(())
     }
 
     // Tokenizer
@@ -246,47 +575,182 @@ impl Parser {
                 break;
             }
         }
-        self.pos = i
+        self.advance_to(i);
     }
 
-    fn lex_num(&mut self) {
+    // Scan a run of decimal digits, allowing '_' as a separator that is
+    // stripped from `out`; rejects a separator at the start, end, or doubled
+    fn lex_decimal_digits(&mut self, out: &mut String) -> Result<(), ParseError> {
+        let start_len = out.len();
+        let mut last_was_sep = false;
+        loop {
+            let c = self.text[self.pos];
+            if c.is_ascii_digit() {
+                out.push(c);
+                last_was_sep = false;
+                self.advance(1);
+            } else if c == '_' {
+                if out.len() == start_len || last_was_sep {
+                    return Err(self.err_at(
+                        self.line,
+                        self.col,
+                        self.pos,
+                        self.pos,
+                        ParseErrorKind::MalformedNumber,
+                        "Misplaced digit separator",
+                    ));
+                }
+                last_was_sep = true;
+                self.advance(1);
+            } else {
+                break;
+            }
+        }
+        if last_was_sep {
+            return Err(self.err_at(
+                self.line,
+                self.col,
+                self.pos,
+                self.pos,
+                ParseErrorKind::MalformedNumber,
+                "Misplaced digit separator",
+            ));
+        }
+        if out.len() == start_len {
+            return Err(self.err_at(
+                self.line,
+                self.col,
+                self.pos,
+                self.pos,
+                ParseErrorKind::MalformedNumber,
+                "Expected digit",
+            ));
+        }
+        Ok(())
+    }
+
+    fn lex_num(&mut self) -> Result<(), ParseError> {
         let i = self.pos;
+        let start_line = self.line;
+        let start_col = self.col;
+
+        // Radix prefix: 0x/0X (hex), 0o/0O (octal), 0b/0B (binary) - no
+        // fraction, exponent, or digit separator rules differ per radix
+        if self.text[self.pos] == '0' {
+            let is_digit: Option<fn(char) -> bool> = match self.text[self.pos + 1] {
+                'x' | 'X' => Some(|c: char| c.is_ascii_hexdigit()),
+                'o' | 'O' => Some(|c: char| ('0'..='7').contains(&c)),
+                'b' | 'B' => Some(|c: char| c == '0' || c == '1'),
+                _ => None,
+            };
+            if let Some(is_digit) = is_digit {
+                self.advance(2);
+                let digits_start = self.pos;
+                while is_digit(self.text[self.pos]) {
+                    self.advance(1);
+                }
+                if self.pos == digits_start {
+                    return Err(self.err_at(
+                        start_line,
+                        start_col,
+                        i,
+                        self.pos,
+                        ParseErrorKind::MalformedNumber,
+                        "Expected digits after radix prefix",
+                    ));
+                }
+                if is_id_part(self.text[self.pos]) || self.text[self.pos] == '.' {
+                    return Err(self.err_at(
+                        start_line,
+                        start_col,
+                        i,
+                        self.pos,
+                        ParseErrorKind::MalformedNumber,
+                        "Invalid character in numeric literal",
+                    ));
+                }
+                let s = substr(&self.text, i, self.pos);
+                self.tok = Tok::Atom(s);
+                return Ok(());
+            }
+        }
 
-        // Integer
-        self.lex_id();
+        // Decimal literal, with '_' allowed as a digit separator
+        let mut s = String::new();
+        let has_int = self.text[self.pos] != '.';
+        if has_int {
+            self.lex_decimal_digits(&mut s)?;
+        }
 
         // Decimal point
-        if self.text[self.pos] == '.' {
-            self.pos += 1;
-            self.lex_id();
+        if self.text[self.pos] == '.' && self.text[self.pos + 1] != '.' {
+            s.push('.');
+            self.advance(1);
+            self.lex_decimal_digits(&mut s)?;
         }
 
         // Exponent
-        match self.text[i + 1] {
-            'x' | 'X' => {}
-            _ => match self.text[self.pos] {
-                'e' | 'E' => {
-                    self.pos += 1;
-                    match self.text[self.pos] {
-                        '+' | '-' => {
-                            self.pos += 1;
-                        }
-                        _ => {}
-                    }
-                    self.lex_id();
-                }
-                _ => {}
-            },
+        if matches!(self.text[self.pos], 'e' | 'E') {
+            s.push(self.text[self.pos]);
+            self.advance(1);
+            if matches!(self.text[self.pos], '+' | '-') {
+                s.push(self.text[self.pos]);
+                self.advance(1);
+            }
+            self.lex_decimal_digits(&mut s)?;
+        }
+
+        if is_id_part(self.text[self.pos]) {
+            return Err(self.err_at(
+                start_line,
+                start_col,
+                i,
+                self.pos,
+                ParseErrorKind::MalformedNumber,
+                "Invalid character in numeric literal",
+            ));
         }
 
-        // Token
-        let s = substr(&self.text, i, self.pos);
         self.tok = Tok::Atom(s);
+        Ok(())
     }
 
-    fn lex(&mut self) {
+    fn lex(&mut self) -> Result<(), ParseError> {
+        self.prev_tok_end = self.tok_src.end_offset;
+
+        // A previous call may have queued several Indent/Dedent tokens for
+        // one line transition; hand them out one at a time before touching
+        // the text again
+        if let Some(t) = self.pending.pop_front() {
+            self.tok = t;
+            self.tok_src = Src {
+                file: self.file.clone(),
+                line: self.line,
+                col: self.col,
+                start_offset: self.pos,
+                end_offset: self.pos,
+            };
+            return Ok(());
+        }
+
         while self.pos < self.text.len() {
+            let start_pos = self.pos;
+            let start_line = self.line;
+            let start_col = self.col;
             let c = self.text[self.pos];
+            macro_rules! tok {
+                ($tok:expr) => {{
+                    self.tok = $tok;
+                    self.tok_src = Src {
+                        file: self.file.clone(),
+                        line: start_line,
+                        col: start_col,
+                        start_offset: start_pos,
+                        end_offset: self.pos,
+                    };
+                    return Ok(());
+                }};
+            }
             match c {
                 '"' | '\'' => {
                     let q = self.text[self.pos];
@@ -294,307 +758,474 @@ impl Parser {
                     while self.text[i] != q {
                         let c = self.text[i];
                         if c == '\n' {
-                            self.err("Unterminated string");
+                            // Advance past the partial string before
+                            // erroring, so that a caller resynchronizing
+                            // after this error (recovering-mode parsing)
+                            // doesn't just re-lex the same unterminated
+                            // quote forever
+                            self.advance_to(i);
+                            return Err(self.err_at(
+                                start_line,
+                                start_col,
+                                start_pos,
+                                i,
+                                ParseErrorKind::Lex,
+                                "Unterminated string",
+                            ));
                         }
                         i += 1;
 
-                        // Backslash can escape many things
-                        // but most of them can be left to the JavaScript compiler to interpret
-                        // The only things we need to worry about here are:
-                        // Escaping a closing quote
-                        // Escaping a backslash that might otherwise escape a closing quote
-                        if c == '\\' && (self.text[i] == q || self.text[i] == '\\') {
-                            i += 1;
+                        // What an escape decodes to can be left to the
+                        // JavaScript compiler to interpret -- Expr::Atom is
+                        // emitted into the generated JS verbatim -- but a
+                        // typo like `\q` should still be a parse error
+                        // here rather than silently becoming whatever JS
+                        // makes of it
+                        if c == '\\' {
+                            let e = char_at(&self.text, i);
+                            match e {
+                                // Everything JS itself recognizes, so a
+                                // program that parsed before this
+                                // validation was added still parses
+                                'n' | 't' | 'r' | 'b' | 'f' | 'v' | '0' | '\\' | '"' | '\''
+                                | '/' => {
+                                    i += 1;
+                                }
+                                'x' => {
+                                    if !char_at(&self.text, i + 1).is_ascii_hexdigit()
+                                        || !char_at(&self.text, i + 2).is_ascii_hexdigit()
+                                    {
+                                        self.advance_to(skip_to_string_end(&self.text, i, q));
+                                        return Err(self.err_at(
+                                            start_line,
+                                            start_col,
+                                            start_pos,
+                                            i,
+                                            ParseErrorKind::Lex,
+                                            "Invalid escape sequence",
+                                        ));
+                                    }
+                                    i += 3;
+                                }
+                                'u' if char_at(&self.text, i + 1) == '{' => {
+                                    let mut j = i + 2;
+                                    while char_at(&self.text, j) != '}' {
+                                        if j >= self.text.len()
+                                            || !char_at(&self.text, j).is_ascii_hexdigit()
+                                        {
+                                            self.advance_to(skip_to_string_end(&self.text, i, q));
+                                            return Err(self.err_at(
+                                                start_line,
+                                                start_col,
+                                                start_pos,
+                                                i,
+                                                ParseErrorKind::Lex,
+                                                "Invalid escape sequence",
+                                            ));
+                                        }
+                                        j += 1;
+                                    }
+                                    // `\u{}` has no hex digits at all, and a
+                                    // code point above 0x10FFFF doesn't
+                                    // exist; JS itself rejects both as a
+                                    // SyntaxError
+                                    let digits: String =
+                                        self.text[i + 2..j].iter().collect();
+                                    let in_range = !digits.is_empty()
+                                        && u32::from_str_radix(&digits, 16)
+                                            .is_ok_and(|cp| cp <= 0x10FFFF);
+                                    if !in_range {
+                                        self.advance_to(skip_to_string_end(&self.text, i, q));
+                                        return Err(self.err_at(
+                                            start_line,
+                                            start_col,
+                                            start_pos,
+                                            i,
+                                            ParseErrorKind::Lex,
+                                            "Invalid escape sequence",
+                                        ));
+                                    }
+                                    i = j + 1;
+                                }
+                                'u' => {
+                                    for k in 1..=4 {
+                                        if !char_at(&self.text, i + k).is_ascii_hexdigit() {
+                                            self.advance_to(skip_to_string_end(&self.text, i, q));
+                                            return Err(self.err_at(
+                                                start_line,
+                                                start_col,
+                                                start_pos,
+                                                i,
+                                                ParseErrorKind::Lex,
+                                                "Invalid escape sequence",
+                                            ));
+                                        }
+                                    }
+                                    i += 5;
+                                }
+                                _ => {
+                                    self.advance_to(skip_to_string_end(&self.text, i, q));
+                                    return Err(self.err_at(
+                                        start_line,
+                                        start_col,
+                                        start_pos,
+                                        i,
+                                        ParseErrorKind::Lex,
+                                        "Invalid escape sequence",
+                                    ));
+                                }
+                            }
                         }
                     }
                     i += 1;
                     let s = substr(&self.text, self.pos, i);
-                    self.pos = i;
-                    self.tok = Tok::Atom(s);
-                    return;
+                    self.advance_to(i);
+                    tok!(Tok::Atom(s));
+                }
+                '`' => {
+                    self.advance(1);
+                    let mut depth = 0usize;
+                    loop {
+                        if self.pos >= self.text.len() {
+                            return Err(self.err_at(
+                                start_line,
+                                start_col,
+                                start_pos,
+                                self.pos,
+                                ParseErrorKind::Lex,
+                                "Unterminated template",
+                            ));
+                        }
+                        let c = self.text[self.pos];
+                        if c == '\\' {
+                            self.advance(1);
+                            if self.pos < self.text.len() {
+                                self.advance(1);
+                            }
+                        } else if depth == 0 && c == '`' {
+                            self.advance(1);
+                            break;
+                        } else if depth == 0 && c == '$' && self.text[self.pos + 1] == '{' {
+                            self.advance(2);
+                            depth = 1;
+                        } else if c == '\n' {
+                            if depth == 0 {
+                                return Err(self.err_at(
+                                    start_line,
+                                    start_col,
+                                    start_pos,
+                                    self.pos,
+                                    ParseErrorKind::Lex,
+                                    "Unterminated template",
+                                ));
+                            }
+                            self.newline();
+                        } else {
+                            if depth > 0 {
+                                if c == '{' {
+                                    depth += 1;
+                                } else if c == '}' {
+                                    depth -= 1;
+                                }
+                            }
+                            self.advance(1);
+                        }
+                    }
+                    let s = substr(&self.text, start_pos, self.pos);
+                    tok!(Tok::Atom(s));
                 }
                 ':' => {
-                    self.pos += 1;
-                    self.tok = Tok::Colon;
-                    return;
+                    self.advance(1);
+                    tok!(Tok::Colon);
                 }
                 '~' => {
-                    self.pos += 1;
-                    self.tok = Tok::BitNot;
-                    return;
+                    self.advance(1);
+                    tok!(Tok::BitNot);
                 }
                 ',' => {
-                    self.pos += 1;
-                    self.tok = Tok::Comma;
-                    return;
+                    self.advance(1);
+                    tok!(Tok::Comma);
                 }
                 '+' => {
-                    self.tok = match self.text[self.pos + 1] {
+                    let r = match self.text[self.pos + 1] {
                         '=' => {
-                            self.pos += 2;
+                            self.advance(2);
                             Tok::AddAssign
                         }
                         _ => {
-                            self.pos += 1;
+                            self.advance(1);
                             Tok::Add
                         }
                     };
-                    return;
+                    tok!(r);
                 }
                 '%' => {
-                    self.tok = match self.text[self.pos + 1] {
+                    let r = match self.text[self.pos + 1] {
                         '=' => {
-                            self.pos += 2;
+                            self.advance(2);
                             Tok::ModAssign
                         }
                         _ => {
-                            self.pos += 1;
+                            self.advance(1);
                             Tok::Mod
                         }
                     };
-                    return;
+                    tok!(r);
                 }
                 '-' => {
-                    self.tok = match self.text[self.pos + 1] {
+                    let r = match self.text[self.pos + 1] {
                         '=' => {
-                            self.pos += 2;
+                            self.advance(2);
                             Tok::SubAssign
                         }
+                        '>' => {
+                            self.advance(2);
+                            Tok::Arrow
+                        }
                         _ => {
-                            self.pos += 1;
+                            self.advance(1);
                             Tok::Minus
                         }
                     };
-                    return;
+                    tok!(r);
                 }
                 '&' => {
-                    self.tok = match self.text[self.pos + 1] {
+                    let r = match self.text[self.pos + 1] {
                         '=' => {
-                            self.pos += 2;
+                            self.advance(2);
                             Tok::BitAndAssign
                         }
                         '&' => {
-                            self.pos += 2;
+                            self.advance(2);
                             Tok::And
                         }
                         _ => {
-                            self.pos += 1;
+                            self.advance(1);
                             Tok::BitAnd
                         }
                     };
-                    return;
+                    tok!(r);
                 }
                 '|' => {
-                    self.tok = match self.text[self.pos + 1] {
+                    let r = match self.text[self.pos + 1] {
                         '=' => {
-                            self.pos += 2;
+                            self.advance(2);
                             Tok::BitOrAssign
                         }
                         '|' => {
-                            self.pos += 2;
+                            self.advance(2);
                             Tok::Or
                         }
+                        '>' => {
+                            self.advance(2);
+                            Tok::PipeForward
+                        }
                         _ => {
-                            self.pos += 1;
+                            self.advance(1);
                             Tok::Pipe
                         }
                     };
-                    return;
+                    tok!(r);
                 }
                 '^' => {
-                    self.tok = match self.text[self.pos + 1] {
+                    let r = match self.text[self.pos + 1] {
                         '=' => {
-                            self.pos += 2;
+                            self.advance(2);
                             Tok::BitXorAssign
                         }
                         _ => {
-                            self.pos += 1;
+                            self.advance(1);
                             Tok::BitXor
                         }
                     };
-                    return;
+                    tok!(r);
                 }
                 '*' => {
-                    self.tok = match self.text[self.pos + 1] {
+                    let r = match self.text[self.pos + 1] {
                         '=' => {
-                            self.pos += 2;
+                            self.advance(2);
                             Tok::MulAssign
                         }
                         '*' => {
                             if self.text[self.pos + 2] == '=' {
-                                self.pos += 3;
+                                self.advance(3);
                                 Tok::PowAssign
                             } else {
-                                self.pos += 2;
+                                self.advance(2);
                                 Tok::Pow
                             }
                         }
                         _ => {
-                            self.pos += 1;
+                            self.advance(1);
                             Tok::Mul
                         }
                     };
-                    return;
+                    tok!(r);
                 }
                 '/' => {
-                    self.tok = match self.text[self.pos + 1] {
+                    let r = match self.text[self.pos + 1] {
                         '=' => {
-                            self.pos += 2;
+                            self.advance(2);
                             Tok::DivAssign
                         }
                         _ => {
-                            self.pos += 1;
+                            self.advance(1);
                             Tok::Div
                         }
                     };
-                    return;
+                    tok!(r);
                 }
                 '(' => {
-                    self.pos += 1;
-                    self.tok = Tok::LParen;
-                    return;
+                    self.advance(1);
+                    tok!(Tok::LParen);
                 }
                 ')' => {
-                    self.pos += 1;
-                    self.tok = Tok::RParen;
-                    return;
+                    self.advance(1);
+                    tok!(Tok::RParen);
                 }
                 '{' => {
-                    self.pos += 1;
-                    self.tok = Tok::LBrace;
-                    return;
+                    self.advance(1);
+                    tok!(Tok::LBrace);
                 }
                 '}' => {
-                    self.pos += 1;
-                    self.tok = Tok::RBrace;
-                    return;
+                    self.advance(1);
+                    tok!(Tok::RBrace);
                 }
                 '[' => {
-                    self.pos += 1;
-                    self.tok = Tok::LSquare;
-                    return;
+                    self.advance(1);
+                    tok!(Tok::LSquare);
                 }
                 ']' => {
-                    self.pos += 1;
-                    self.tok = Tok::RSquare;
-                    return;
+                    self.advance(1);
+                    tok!(Tok::RSquare);
                 }
                 '.' => {
                     if self.text[self.pos + 1].is_ascii_digit() {
-                        self.lex_num();
-                        return;
+                        self.lex_num()?;
+                        tok!(self.tok.clone());
                     }
-                    self.pos += 1;
-                    self.tok = Tok::Dot;
-                    return;
+                    if self.text[self.pos + 1] == '.' && self.text[self.pos + 2] == '.' {
+                        self.advance(3);
+                        tok!(Tok::Ellipsis);
+                    }
+                    self.advance(1);
+                    tok!(Tok::Dot);
                 }
                 '=' => {
-                    self.tok = match self.text[self.pos + 1] {
+                    let r = match self.text[self.pos + 1] {
                         '=' => {
-                            self.pos += 2;
+                            self.advance(2);
                             Tok::Eq
                         }
                         _ => {
-                            self.pos += 1;
+                            self.advance(1);
                             Tok::Assign
                         }
                     };
-                    return;
+                    tok!(r);
                 }
                 '\n' | '#' => {
+                    // Start of the line we'll eventually land on; reset
+                    // every time we actually cross a '\n', so blank lines
+                    // and comment-only lines in between don't count
+                    let mut indent_start = self.pos;
                     while self.pos < self.text.len() {
                         let c = self.text[self.pos];
                         if c.is_whitespace() {
                             if c == '\n' {
-                                self.line += 1;
+                                self.newline();
+                                indent_start = self.pos;
+                            } else {
+                                self.advance(1);
                             }
-                            self.pos += 1;
                         } else if c == '#' {
                             while self.text[self.pos] != '\n' {
-                                self.pos += 1;
+                                self.advance(1);
                             }
                         } else {
                             break;
                         }
                     }
-                    self.tok = Tok::Newline;
-                    return;
+                    if self.offside {
+                        self.offside_transition(indent_start)?;
+                    }
+                    tok!(Tok::Newline);
                 }
                 '<' => {
-                    self.tok = match self.text[self.pos + 1] {
+                    let r = match self.text[self.pos + 1] {
                         '=' => {
-                            self.pos += 2;
+                            self.advance(2);
                             Tok::Le
                         }
                         '<' => {
                             if self.text[self.pos + 2] == '=' {
-                                self.pos += 3;
+                                self.advance(3);
                                 Tok::ShlAssign
                             } else {
-                                self.pos += 2;
+                                self.advance(2);
                                 Tok::Shl
                             }
                         }
                         _ => {
-                            self.pos += 1;
+                            self.advance(1);
                             Tok::Lt
                         }
                     };
-                    return;
+                    tok!(r);
                 }
                 '!' => {
-                    self.tok = match self.text[self.pos + 1] {
+                    let r = match self.text[self.pos + 1] {
                         '=' => {
-                            self.pos += 2;
+                            self.advance(2);
                             Tok::Ne
                         }
                         _ => {
-                            self.pos += 1;
+                            self.advance(1);
                             Tok::Not
                         }
                     };
-                    return;
+                    tok!(r);
                 }
                 '>' => {
-                    self.tok = match self.text[self.pos + 1] {
+                    let r = match self.text[self.pos + 1] {
                         '=' => {
-                            self.pos += 2;
+                            self.advance(2);
                             Tok::Ge
                         }
                         '>' => match self.text[self.pos + 2] {
                             '=' => {
-                                self.pos += 3;
+                                self.advance(3);
                                 Tok::ShrAssign
                             }
                             '>' => {
                                 if self.text[self.pos + 3] == '=' {
-                                    self.pos += 4;
+                                    self.advance(4);
                                     Tok::LShrAssign
                                 } else {
-                                    self.pos += 3;
+                                    self.advance(3);
                                     Tok::LShr
                                 }
                             }
                             _ => {
-                                self.pos += 2;
+                                self.advance(2);
                                 Tok::Shr
                             }
                         },
                         _ => {
-                            self.pos += 1;
+                            self.advance(1);
                             Tok::Gt
                         }
                     };
-                    return;
+                    tok!(r);
                 }
                 _ => {
                     if c.is_whitespace() {
-                        self.pos += 1;
+                        self.advance(1);
                         continue;
                     }
                     if c.is_ascii_digit() {
-                        self.lex_num();
-                        return;
+                        self.lex_num()?;
+                        tok!(self.tok.clone());
                     }
                     if is_id_part(c) {
                         let i = self.pos;
@@ -604,238 +1235,534 @@ impl Parser {
                         let s = substr(&self.text, i, self.pos);
 
                         // Keyword?
-                        self.tok = match self.keywords.get(&s) {
+                        let r = match self.keywords.get(&s) {
                             Some(tok) => tok.clone(),
                             None => Tok::Atom(s),
                         };
 
-                        return;
+                        tok!(r);
                     }
-                    self.err("Unknown character");
+                    // Advance past it before erroring, same as the string
+                    // lexer above, so a caller resynchronizing after this
+                    // error doesn't just re-lex the same character forever
+                    let e = self.err_at(
+                        start_line,
+                        start_col,
+                        start_pos,
+                        self.pos,
+                        ParseErrorKind::Lex,
+                        "Unknown character",
+                    );
+                    self.advance(1);
+                    return Err(e);
                 }
             }
         }
         self.tok = Tok::Eof;
+        self.tok_src = Src {
+            file: self.file.clone(),
+            line: self.line,
+            col: self.col,
+            start_offset: self.pos,
+            end_offset: self.pos,
+        };
+        Ok(())
     }
 
-    fn eat(&mut self, tok: Tok) -> bool {
+    fn eat(&mut self, tok: Tok) -> Result<bool, ParseError> {
         if self.tok == tok {
-            self.lex();
-            return true;
+            self.lex()?;
+            return Ok(true);
         }
-        false
+        Ok(false)
     }
 
-    fn require(&mut self, tok: Tok, s: &str) {
-        if !self.eat(tok) {
-            self.err(format!("Expected {}", s));
+    fn require(&mut self, tok: Tok, s: &str) -> Result<(), ParseError> {
+        // The three most common requirements get their own ParseErrorKind,
+        // so a caller (an LSP, a test harness) can react to "you forgot an
+        // 'end'" without pattern-matching on `message`
+        let kind = match tok {
+            Tok::RParen => ParseErrorKind::MissingRParen,
+            Tok::End => ParseErrorKind::MissingEnd,
+            Tok::Newline => ParseErrorKind::ExpectedNewline,
+            _ => ParseErrorKind::Syntax,
+        };
+        if !self.eat(tok)? {
+            return Err(self.err(kind, format!("Expected {}", s)));
         }
+        Ok(())
     }
 
-    fn atom(&mut self) -> String {
+    // Like `require(Tok::Newline, "newline")`, but recovers instead of
+    // aborting the whole enclosing statement: a statement header (`if
+    // cond`, `while cond`, ...) is followed by a newline and then the
+    // first token of its body, and it's that *next* token's lex, not the
+    // newline itself, that most often fails (a bad character right at the
+    // start of the body). An unguarded `?` here would let that failure
+    // unwind past `block()`'s own synchronization and collapse the entire
+    // enclosing construct into a single placeholder instead of just the
+    // one bad line inside it.
+    fn require_newline_recovering(&mut self) -> Result<(), ParseError> {
+        if let Err(e) = self.require(Tok::Newline, "newline") {
+            if !self.recovering {
+                return Err(e);
+            }
+            self.record_error(e);
+        }
+        Ok(())
+    }
+
+    fn atom(&mut self) -> Result<String, ParseError> {
         if let Tok::Atom(s) = &self.tok {
             let s = s.clone();
-            self.lex();
-            return s;
+            self.lex()?;
+            return Ok(s);
         }
-        self.err("Expected name");
+        Err(self.err(ParseErrorKind::Syntax, "Expected name"))
     }
 
-    fn id(&mut self) -> String {
+    fn id(&mut self) -> Result<String, ParseError> {
         if let Tok::Atom(s) = &self.tok {
             let c = s.chars().nth(0).unwrap();
             if is_id_start(c) {
                 let s = s.clone();
-                self.lex();
-                return s;
+                self.lex()?;
+                return Ok(s);
             }
         }
-        self.err("Expected identifier");
+        Err(self.err(ParseErrorKind::Syntax, "Expected identifier"))
     }
 
-    fn str_literal(&mut self) -> String {
+    fn str_literal(&mut self) -> Result<String, ParseError> {
         if let Tok::Atom(s) = &self.tok {
             let c = s.chars().nth(0).unwrap();
             if c == '\'' || c == '"' {
                 let s = s.clone();
-                self.lex();
-                return s;
+                self.lex()?;
+                return Ok(s);
             }
         }
-        self.err("Expected string");
+        Err(self.err(ParseErrorKind::Syntax, "Expected string"))
+    }
+
+    // Lower a `` `...${...}...` `` template token (backticks included) into
+    // literal-chunk and interpolated-expression pieces, concatenated
+    fn template(&self, s: &str, src: Src) -> Result<Expr, ParseError> {
+        let chars: Vec<char> = s.chars().collect();
+        let end = chars.len() - 1;
+        let mut i = 1; // skip the opening backtick
+        let mut pieces = Vec::<Expr>::new();
+        let mut literal = String::new();
+        while i < end {
+            let c = chars[i];
+            if c == '\\' && i + 1 < end {
+                literal.push(chars[i + 1]);
+                i += 2;
+                continue;
+            }
+            if c == '$' && i + 1 < end && chars[i + 1] == '{' {
+                if !literal.is_empty() {
+                    pieces.push(Expr::Atom(src.clone(), format!("{:?}", literal)));
+                    literal = String::new();
+                }
+                i += 2;
+                let expr_start = i;
+                let mut depth = 1;
+                while depth > 0 {
+                    match chars[i] {
+                        '{' => depth += 1,
+                        '}' => depth -= 1,
+                        _ => {}
+                    }
+                    if depth > 0 {
+                        i += 1;
+                    }
+                }
+                let inner: String = chars[expr_start..i].iter().collect();
+                i += 1; // skip the closing '}'
+                pieces.push(self.parse_interpolation(&inner)?);
+                continue;
+            }
+            literal.push(c);
+            i += 1;
+        }
+        if !literal.is_empty() || pieces.is_empty() {
+            pieces.push(Expr::Atom(src.clone(), format!("{:?}", literal)));
+        }
+        Ok(Expr::Template(src, pieces))
+    }
+
+    // An interpolated `${...}` is parsed as its own little program; its
+    // spans are relative to the interpolation text, not the outer file
+    fn parse_interpolation(&self, s: &str) -> Result<Expr, ParseError> {
+        let mut text: Vec<char> = s.chars().collect();
+        text.push('\n');
+        let mut p = Parser::new(self.file.clone(), text);
+        p.lex()?;
+        p.expr()
     }
 
     // Expressions
-    fn comma_separated(&mut self, v: &mut Vec<Expr>, end: Tok) {
+    // Shared by call arguments and list literals. Each iteration parses
+    // exactly one item with a single `expr()` call and then explicitly
+    // checks for `Tok::Comma` to continue, so there's no ambiguity for
+    // `expr()` to resolve: `Comma` is never registered in `ops` (see
+    // `Parser::new`), so `infix` always stops there on its own regardless
+    // of context, and nested comma-separated contexts (a list literal
+    // inside a call's arguments, or vice versa) each close their own
+    // bracket before the enclosing loop ever sees its separator
+    fn comma_separated(&mut self, v: &mut Vec<Expr>, end: Tok) -> Result<(), ParseError> {
         if self.tok == end {
-            return;
+            return Ok(());
         }
         loop {
-            v.push(self.expr());
-            if !self.eat(Tok::Comma) {
+            v.push(self.expr()?);
+            if !self.eat(Tok::Comma)? {
                 break;
             }
         }
+        Ok(())
     }
 
-    fn primary(&mut self) -> Expr {
+    // A comma-separated list of `case` patterns, as found after a `|`
+    fn patterns(&mut self, v: &mut Vec<Pattern>) -> Result<(), ParseError> {
+        loop {
+            v.push(self.pattern()?);
+            if !self.eat(Tok::Comma)? {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    // A pattern, plus the optional `if` guard following it
+    fn pattern(&mut self) -> Result<Pattern, ParseError> {
+        let p = self.pattern_primary()?;
+        if self.eat(Tok::If)? {
+            let cond = self.expr()?;
+            return Ok(Pattern::Guard(Box::new(p), cond));
+        }
+        Ok(p)
+    }
+
+    // `_` is a wildcard, a lowercase name binds the subject, a capitalized
+    // name or any other atom (number, string...) is a literal to compare
+    // against, and `[...]` destructures a list, with a trailing `...rest`
+    // standing for everything not matched by the elements before it
+    fn pattern_primary(&mut self) -> Result<Pattern, ParseError> {
+        let start = self.tok_src.clone();
         match &self.tok {
+            Tok::LSquare => {
+                self.lex()?;
+                let mut elems = Vec::<Pattern>::new();
+                let mut rest = None;
+                if self.tok != Tok::RSquare {
+                    loop {
+                        if self.eat(Tok::Ellipsis)? {
+                            rest = Some(Box::new(self.pattern_primary()?));
+                            break;
+                        }
+                        elems.push(self.pattern_primary()?);
+                        if !self.eat(Tok::Comma)? {
+                            break;
+                        }
+                    }
+                }
+                self.require(Tok::RSquare, "']'")?;
+                Ok(Pattern::List(elems, rest))
+            }
+            Tok::Atom(s) if is_id_start(s.chars().next().unwrap()) => {
+                let s = s.clone();
+                self.lex()?;
+                if s == "_" {
+                    Ok(Pattern::Wildcard)
+                } else if s.chars().next().unwrap().is_uppercase() {
+                    Ok(Pattern::Literal(Expr::Atom(self.span(&start), s)))
+                } else {
+                    Ok(Pattern::Bind(s))
+                }
+            }
+            _ => Ok(Pattern::Literal(self.primary()?)),
+        }
+    }
+
+    // In recovery mode, an error anywhere under `primary_inner` (including
+    // a failed nested `require`/`comma_separated` for a list/object/paren
+    // expression) is caught here instead of aborting the whole parse
+    fn primary(&mut self) -> Result<Expr, ParseError> {
+        match self.primary_inner() {
+            Ok(a) => Ok(a),
+            Err(e) if self.recovering => {
+                let src = e.src.clone();
+                self.record_error(e);
+                Ok(Expr::Error(src))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn primary_inner(&mut self) -> Result<Expr, ParseError> {
+        let start = self.tok_src.clone();
+        let r = match &self.tok {
             Tok::LSquare => {
                 let mut v = Vec::<Expr>::new();
-                self.lex();
-                self.comma_separated(&mut v, Tok::RSquare);
-                self.require(Tok::RSquare, "']'");
-                Expr::List(v)
+                self.lex()?;
+                self.comma_separated(&mut v, Tok::RSquare)?;
+                self.require(Tok::RSquare, "']'")?;
+                Expr::List(self.span(&start), v)
             }
             Tok::LBrace => {
                 let mut v = Vec::<Expr>::new();
-                self.lex();
+                self.lex()?;
                 if self.tok != Tok::RBrace {
                     loop {
-                        let k = self.atom();
-                        v.push(Expr::Atom(k));
-                        self.require(Tok::Colon, "':'");
-                        v.push(self.expr());
-                        if !self.eat(Tok::Comma) {
+                        let key_start = self.tok_src.clone();
+                        let k = self.atom()?;
+                        v.push(Expr::Atom(self.span(&key_start), k));
+                        self.require(Tok::Colon, "':'")?;
+                        v.push(self.expr()?);
+                        if !self.eat(Tok::Comma)? {
                             break;
                         }
                     }
                 }
-                self.require(Tok::RBrace, "'}'");
-                Expr::Object(v)
+                self.require(Tok::RBrace, "'}'")?;
+                Expr::Object(self.span(&start), v)
             }
             Tok::LParen => {
-                self.lex();
-                let a = self.expr();
-                self.require(Tok::RParen, "')'");
+                self.lex()?;
+                let a = self.expr()?;
+                self.require(Tok::RParen, "')'")?;
                 a
             }
             Tok::Atom(s) => {
                 let s = s.clone();
-                self.lex();
-                Expr::Atom(s)
+                self.lex()?;
+                if s.starts_with('`') {
+                    self.template(&s, self.span(&start))?
+                } else {
+                    Expr::Atom(self.span(&start), s)
+                }
             }
             _ => {
-                self.err(format!("{:?}: Expected expression", self.tok));
+                return Err(self.err(
+                    ParseErrorKind::Syntax,
+                    format!("{:?}: Expected expression", self.tok),
+                ));
             }
+        };
+        Ok(r)
+    }
+
+    // Same recovery boundary as `primary`, for the `.`/`[...]`/`(...)`
+    // postfix operators built on top of it
+    fn postfix(&mut self) -> Result<Expr, ParseError> {
+        match self.postfix_inner() {
+            Ok(a) => Ok(a),
+            Err(e) if self.recovering => {
+                let src = e.src.clone();
+                self.record_error(e);
+                Ok(Expr::Error(src))
+            }
+            Err(e) => Err(e),
         }
     }
 
-    fn postfix(&mut self) -> Expr {
-        let mut a = self.primary();
+    fn postfix_inner(&mut self) -> Result<Expr, ParseError> {
+        let start = self.tok_src.clone();
+        let mut a = self.primary()?;
         loop {
             a = match &self.tok {
                 Tok::Dot => {
                     let a = Box::new(a);
-                    self.lex();
+                    self.lex()?;
 
-                    let k = self.id();
+                    let key_start = self.tok_src.clone();
+                    let k = self.id()?;
                     let k = format!("{:?}", k);
-                    let k = Expr::Atom(k);
+                    let k = Expr::Atom(self.span(&key_start), k);
                     let k = Box::new(k);
 
-                    Expr::Subscript(a, k)
+                    Expr::Subscript(self.span(&start), a, k)
                 }
                 Tok::LSquare => {
                     let a = Box::new(a);
-                    self.lex();
+                    self.lex()?;
 
                     // First subscript
                     let i = match self.tok {
-                        Tok::Colon => Expr::Atom("0".to_string()),
-                        _ => self.expr(),
+                        Tok::Colon => Expr::Atom(self.src(), "0".to_string()),
+                        _ => self.expr()?,
                     };
                     let i = Box::new(i);
 
                     // Second subscript?
                     let a = match self.tok {
-                        Tok::RSquare => Expr::Subscript(a, i),
+                        Tok::RSquare => Expr::Subscript(self.span(&start), a, i),
                         Tok::Colon => {
-                            self.lex();
+                            self.lex()?;
 
                             let j = match self.tok {
-                                Tok::RSquare => Expr::Atom("undefined".to_string()),
-                                _ => self.expr(),
+                                Tok::RSquare => Expr::Atom(self.src(), "undefined".to_string()),
+                                _ => self.expr()?,
                             };
                             let j = Box::new(j);
 
-                            Expr::Slice(a, i, j)
+                            Expr::Slice(self.span(&start), a, i, j)
                         }
                         _ => {
-                            self.err(format!("{:?}: Expected ':' or ']'", self.tok));
+                            return Err(self.err(
+                                ParseErrorKind::Syntax,
+                                format!("{:?}: Expected ':' or ']'", self.tok),
+                            ));
                         }
                     };
 
-                    self.require(Tok::RSquare, "']'");
+                    self.require(Tok::RSquare, "']'")?;
                     a
                 }
                 Tok::LParen => {
                     let mut v = Vec::<Expr>::new();
-                    self.lex();
-                    self.comma_separated(&mut v, Tok::RParen);
-                    self.require(Tok::RParen, "')'");
-                    Expr::Call(Box::new(a), v)
+                    self.lex()?;
+                    self.comma_separated(&mut v, Tok::RParen)?;
+                    self.require(Tok::RParen, "')'")?;
+                    Expr::Call(self.span(&start), Box::new(a), v)
                 }
                 _ => {
-                    return a;
+                    return Ok(a);
                 }
             };
         }
     }
 
-    fn prefix(&mut self) -> Expr {
-        match &self.tok {
+    // Speculatively parses a lambda parameter list: either a bare
+    // identifier (the `x -> expr` shorthand) or a parenthesized,
+    // comma-separated list (`(a, b) -> expr`), immediately followed by
+    // '->'. Returns None and rewinds the lexer if the lookahead doesn't
+    // pan out, so the caller can fall back to parsing an ordinary atom or
+    // grouped expression instead.
+    fn try_lambda_params(&mut self) -> Result<Option<Vec<String>>, ParseError> {
+        let checkpoint = self.checkpoint();
+        let params = if self.tok == Tok::LParen {
+            self.lex()?;
+            let mut v = Vec::<String>::new();
+            if self.tok != Tok::RParen {
+                loop {
+                    match self.id() {
+                        Ok(name) => v.push(name),
+                        Err(_) => {
+                            self.restore(checkpoint);
+                            return Ok(None);
+                        }
+                    }
+                    if !self.eat(Tok::Comma)? {
+                        break;
+                    }
+                }
+            }
+            if !self.eat(Tok::RParen)? {
+                self.restore(checkpoint);
+                return Ok(None);
+            }
+            v
+        } else if matches!(self.tok, Tok::Atom(_)) {
+            match self.id() {
+                Ok(name) => vec![name],
+                Err(_) => return Ok(None),
+            }
+        } else {
+            return Ok(None);
+        };
+
+        if self.tok == Tok::Arrow {
+            Ok(Some(params))
+        } else {
+            self.restore(checkpoint);
+            Ok(None)
+        }
+    }
+
+    fn prefix(&mut self) -> Result<Expr, ParseError> {
+        let start = self.tok_src.clone();
+        if matches!(self.tok, Tok::LParen | Tok::Atom(_)) {
+            if let Some(params) = self.try_lambda_params()? {
+                self.lex()?; // Consume '->'
+                return if self.eat(Tok::Newline)? {
+                    let mut body = Vec::<Stmt>::new();
+                    let indented = self.block(&mut body)?;
+                    self.end_block(indented)?;
+                    Ok(Expr::LambdaBlock(self.span(&start), params, body))
+                } else {
+                    let body = self.expr()?;
+                    Ok(Expr::Lambda(self.span(&start), params, Box::new(body)))
+                };
+            }
+        }
+        let r = match &self.tok {
             Tok::Typeof => {
-                self.lex();
-                let a = self.prefix();
-                Expr::Typeof(Box::new(a))
+                self.lex()?;
+                let a = self.prefix()?;
+                Expr::Typeof(self.span(&start), Box::new(a))
             }
             Tok::Not => {
-                self.lex();
-                let a = self.prefix();
-                Expr::Prefix("!".to_string(), Box::new(a))
+                self.lex()?;
+                let a = self.prefix()?;
+                Expr::Prefix(self.span(&start), "!".to_string(), Box::new(a))
             }
             Tok::Minus => {
-                self.lex();
-                let a = self.prefix();
-                Expr::Prefix("-".to_string(), Box::new(a))
+                self.lex()?;
+                let a = self.prefix()?;
+                Expr::Prefix(self.span(&start), "-".to_string(), Box::new(a))
             }
             Tok::BitNot => {
-                self.lex();
-                let a = self.prefix();
-                Expr::Prefix("~".to_string(), Box::new(a))
+                self.lex()?;
+                let a = self.prefix()?;
+                Expr::Prefix(self.span(&start), "~".to_string(), Box::new(a))
             }
-            _ => self.postfix(),
-        }
+            _ => return self.postfix(),
+        };
+        Ok(r)
     }
 
-    fn infix(&mut self, prec: u8) -> Expr {
+    fn infix(&mut self, prec: u8) -> Result<Expr, ParseError> {
         // Operator precedence parser
-        let mut a = self.prefix();
+        let start = self.tok_src.clone();
+        let mut a = self.prefix()?;
         loop {
             let o = match self.ops.get(&self.tok) {
                 Some(o) => o.clone(),
-                None => return a,
+                None => return Ok(a),
             };
             if o.prec < prec {
-                return a;
+                return Ok(a);
             }
-            self.lex();
-            let b = self.infix(o.prec + o.left);
+            self.lex()?;
+            let b = self.infix(o.prec + o.left)?;
             a = if o.s == "=" {
-                Expr::Assign(Box::new(a), Box::new(b))
+                Expr::Assign(self.span(&start), Box::new(a), Box::new(b))
             } else if o.compound_assign {
                 Expr::Assign(
+                    self.span(&start),
                     Box::new(a.clone()),
-                    Box::new(Expr::Infix(o.s, Box::new(a), Box::new(b))),
+                    Box::new(Expr::Infix(self.span(&start), o.s, Box::new(a), Box::new(b))),
                 )
+            } else if o.s == "|>" {
+                // Desugar `a |> f(args)` to `f(a, args)`, and `a |> f` (no
+                // call) to `f(a)`, so the interpreter never sees `|>`
+                match b {
+                    Expr::Call(_, f, mut args) => {
+                        args.insert(0, a);
+                        Expr::Call(self.span(&start), f, args)
+                    }
+                    _ => Expr::Call(self.span(&start), Box::new(b), vec![a]),
+                }
             } else {
-                Expr::Infix(o.s, Box::new(a), Box::new(b))
+                Expr::Infix(self.span(&start), o.s, Box::new(a), Box::new(b))
             }
         }
     }
 
-    fn expr(&mut self) -> Expr {
+    fn expr(&mut self) -> Result<Expr, ParseError> {
         self.infix(0)
     }
 
@@ -843,183 +1770,212 @@ impl Parser {
     fn block_end(&self) -> bool {
         matches!(
             self.tok,
-            Tok::Pipe | Tok::Catch | Tok::Else | Tok::Elif | Tok::End | Tok::Eof
+            Tok::Pipe | Tok::Catch | Tok::Dedent | Tok::Else | Tok::Elif | Tok::End | Tok::Eof
         )
     }
 
-    fn if1(&mut self) -> Stmt {
+    fn if1(&mut self) -> Result<Stmt, ParseError> {
         assert!(matches!(self.tok, Tok::If | Tok::Elif));
         let src = self.src();
-        self.lex();
-        let cond = self.expr();
-        self.require(Tok::Newline, "newline");
+        self.lex()?;
+        let cond = self.expr()?;
+        self.require_newline_recovering()?;
 
         // Then
         let mut yes = Vec::<Stmt>::new();
-        self.block(&mut yes);
+        let indented = self.block(&mut yes)?;
 
         // Else
         let mut no = Vec::<Stmt>::new();
         match self.tok {
             Tok::Else => {
-                self.lex();
-                self.require(Tok::Newline, "newline");
-                self.block(&mut no);
-                self.require(Tok::End, "'end'");
+                self.lex()?;
+                self.require_newline_recovering()?;
+                let indented = self.block(&mut no)?;
+                self.end_block(indented)?;
             }
             Tok::Elif => {
-                no.push(self.if1());
+                no.push(self.if1()?);
             }
             _ => {
-                self.require(Tok::End, "'end'");
+                self.end_block(indented)?;
             }
         }
 
-        Stmt::If(src, cond, yes, no)
+        Ok(Stmt::If(src, cond, yes, no))
     }
 
-    fn stmt(&mut self, v: &mut Vec<Stmt>) {
+    // Same recovery boundary as `primary`/`postfix`, one level up: an
+    // unrecoverable error anywhere in parsing a single statement becomes
+    // an `Stmt::Error` placeholder instead of aborting the whole parse
+    fn stmt(&mut self, v: &mut Vec<Stmt>) -> Result<(), ParseError> {
+        match self.stmt_inner(v) {
+            Ok(()) => Ok(()),
+            Err(e) if self.recovering => {
+                let src = e.src.clone();
+                self.record_error(e);
+                v.push(Stmt::Error(src));
+                Ok(())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn stmt_inner(&mut self, v: &mut Vec<Stmt>) -> Result<(), ParseError> {
         let src = self.src();
         let r = match self.tok {
             Tok::Func => {
-                self.lex();
+                self.lex()?;
 
                 // Name
-                let name = self.id();
+                let name = self.id()?;
 
                 // Parameters
-                self.require(Tok::LParen, "'('");
+                self.require(Tok::LParen, "'('")?;
                 let mut params = Vec::<String>::new();
                 if self.tok != Tok::RParen {
                     loop {
-                        params.push(self.id());
-                        if !self.eat(Tok::Comma) {
+                        params.push(self.id()?);
+                        if !self.eat(Tok::Comma)? {
                             break;
                         }
                     }
                 }
-                self.require(Tok::RParen, "')'");
-                self.require(Tok::Newline, "newline");
+                self.require(Tok::RParen, "')'")?;
+                self.require_newline_recovering()?;
 
                 // Outer variables
                 let mut outers = HashSet::<String>::new();
-                while self.eat(Tok::Outer) {
+                while self.eat(Tok::Outer)? {
                     loop {
-                        outers.insert(self.id());
-                        if !self.eat(Tok::Comma) {
+                        outers.insert(self.id()?);
+                        if !self.eat(Tok::Comma)? {
                             break;
                         }
                     }
-                    self.require(Tok::Newline, "newline");
+                    self.require_newline_recovering()?;
                 }
 
                 // Body
                 let mut body = Vec::<Stmt>::new();
-                self.block(&mut body);
+                let indented = self.block(&mut body)?;
 
                 // End
-                self.require(Tok::End, "'end'");
+                self.end_block(indented)?;
                 Stmt::Func(src, name, params, outers, body)
             }
             Tok::For => {
-                self.lex();
-                let item = self.id();
+                self.lex()?;
+                let item = self.id()?;
                 match self.tok {
                     Tok::Colon => {
-                        self.lex();
-                        let collection = self.expr();
-                        self.require(Tok::Newline, "newline");
+                        self.lex()?;
+                        let collection = self.expr()?;
+                        self.require_newline_recovering()?;
 
                         // Body
                         let mut body = Vec::<Stmt>::new();
-                        self.block(&mut body);
+                        let indented = self.block(&mut body)?;
 
                         // End
-                        self.require(Tok::End, "'end'");
+                        self.end_block(indented)?;
                         Stmt::For(src, item, collection, body)
                     }
                     Tok::Comma => {
                         let idx = item;
-                        self.lex();
-                        let item = self.id();
-                        self.require(Tok::Colon, "':'");
-                        let collection = self.expr();
-                        self.require(Tok::Newline, "newline");
+                        self.lex()?;
+                        let item = self.id()?;
+                        self.require(Tok::Colon, "':'")?;
+                        let collection = self.expr()?;
+                        self.require_newline_recovering()?;
 
                         // Body
                         let mut body = Vec::<Stmt>::new();
-                        self.block(&mut body);
+                        let indented = self.block(&mut body)?;
 
                         // End
-                        self.require(Tok::End, "'end'");
+                        self.end_block(indented)?;
                         Stmt::For2(src, idx, item, collection, body)
                     }
                     _ => {
-                        self.err(format!("{:?}: Expected ',' or ':'", self.tok));
+                        return Err(self.err(
+                            ParseErrorKind::Syntax,
+                            format!("{:?}: Expected ',' or ':'", self.tok),
+                        ));
                     }
                 }
             }
             Tok::While => {
-                self.lex();
-                let cond = self.expr();
-                self.require(Tok::Newline, "newline");
+                self.lex()?;
+                let cond = self.expr()?;
+                self.require_newline_recovering()?;
 
                 // Body
                 let mut body = Vec::<Stmt>::new();
-                self.block(&mut body);
+                let indented = self.block(&mut body)?;
 
                 // End
-                self.require(Tok::End, "'end'");
+                self.end_block(indented)?;
                 Stmt::While(src, cond, body)
             }
             Tok::Dowhile => {
-                self.lex();
-                let cond = self.expr();
-                self.require(Tok::Newline, "newline");
+                self.lex()?;
+                let cond = self.expr()?;
+                self.require_newline_recovering()?;
 
                 // Body
                 let mut body = Vec::<Stmt>::new();
-                self.block(&mut body);
+                let indented = self.block(&mut body)?;
 
                 // End
-                self.require(Tok::End, "'end'");
+                self.end_block(indented)?;
                 Stmt::Dowhile(src, cond, body)
             }
             Tok::Assert => {
-                self.lex();
-                let cond = self.expr();
-                let msg = if self.eat(Tok::Comma) {
-                    format!("{:?}", self.str_literal())
+                self.lex()?;
+                let cond = self.expr()?;
+                let msg = if self.eat(Tok::Comma)? {
+                    format!("{:?}", self.str_literal()?)
                 } else {
                     "".to_string()
                 };
                 Stmt::Assert(src, cond, msg)
             }
-            Tok::If => self.if1(),
+            Tok::If => self.if1()?,
             Tok::Case => {
-                self.lex();
-                let subject = self.expr();
-                self.require(Tok::Newline, "newline");
-
-                let mut cases = Vec::<(Vec<Expr>, Vec<Stmt>)>::new();
-                while !self.eat(Tok::End) {
+                self.lex()?;
+                let subject = self.expr()?;
+                self.require_newline_recovering()?;
+                let indented = self.eat(Tok::Indent)?;
+
+                let mut cases = Vec::<(Vec<Pattern>, Vec<Stmt>)>::new();
+                while !(if indented {
+                    self.eat(Tok::Dedent)?
+                } else {
+                    self.eat(Tok::End)?
+                }) {
                     // Patterns
-                    let mut patterns = Vec::<Expr>::new();
+                    let mut patterns = Vec::<Pattern>::new();
                     match self.tok {
                         Tok::Pipe => {
-                            self.lex();
-                            self.comma_separated(&mut patterns, Tok::Eof);
+                            self.lex()?;
+                            self.patterns(&mut patterns)?;
                         }
                         Tok::Else => {
-                            self.lex();
+                            self.lex()?;
+                        }
+                        _ => {
+                            return Err(self.err(
+                                ParseErrorKind::Syntax,
+                                format!("{:?}: Expected '|' or 'else'", self.tok),
+                            ));
                         }
-                        _ => self.err(format!("{:?}: Expected '|' or 'else'", self.tok)),
                     }
-                    self.require(Tok::Newline, "newline");
+                    self.require_newline_recovering()?;
 
                     // Body
                     let mut body = Vec::<Stmt>::new();
-                    self.block(&mut body);
+                    self.block(&mut body)?;
 
                     // Case
                     cases.push((patterns, body));
@@ -1027,104 +1983,230 @@ impl Parser {
                 Stmt::Case(src, subject, cases)
             }
             Tok::Try => {
-                self.lex();
-                self.require(Tok::Newline, "newline");
+                self.lex()?;
+                self.require_newline_recovering()?;
 
                 // Normal path
                 let mut normal = Vec::<Stmt>::new();
-                self.block(&mut normal);
+                self.block(&mut normal)?;
 
                 // Fallback path
                 let mut fallback = Vec::<Stmt>::new();
-                self.require(Tok::Catch, "'catch'");
-                let name = self.id();
-                self.require(Tok::Newline, "newline");
-                self.block(&mut fallback);
+                self.require(Tok::Catch, "'catch'")?;
+                let name = self.id()?;
+                self.require_newline_recovering()?;
+                let indented = self.block(&mut fallback)?;
 
                 // End
-                self.require(Tok::End, "'end'");
+                self.end_block(indented)?;
                 Stmt::Try(src, normal, name, fallback)
             }
             Tok::Return => {
-                self.lex();
+                self.lex()?;
                 let a = if self.tok == Tok::Newline {
-                    Expr::Atom("null".to_string())
+                    Expr::Atom(self.src(), "null".to_string())
                 } else {
-                    self.expr()
+                    self.expr()?
                 };
                 Stmt::Return(src, a)
             }
             Tok::Throw => {
-                self.lex();
-                let a = self.expr();
+                self.lex()?;
+                let a = self.expr()?;
                 Stmt::Throw(src, a)
             }
             _ => {
-                let a = self.expr();
+                let a = self.expr()?;
                 match self.tok {
                     Tok::Colon => {
-                        if let Expr::Atom(s) = a {
-                            self.lex();
+                        if let Expr::Atom(_, s) = a {
+                            self.lex()?;
                             v.push(Stmt::Label(src, s));
-                            return;
+                            return Ok(());
                         }
-                        self.err("':': Label must be an identifier")
+                        return Err(
+                            self.err(ParseErrorKind::BadLabel, "':': Label must be an identifier")
+                        );
                     }
                     Tok::Newline => Stmt::Expr(src, a),
                     _ => {
                         let mut w = Vec::<Expr>::new();
-                        self.comma_separated(&mut w, Tok::Newline);
-                        let a = Expr::Call(Box::new(a), w);
+                        self.comma_separated(&mut w, Tok::Newline)?;
+                        let call_src = a.src().clone();
+                        let a = Expr::Call(call_src, Box::new(a), w);
                         Stmt::Expr(src, a)
                     }
                 }
             }
         };
         v.push(r);
+        Ok(())
     }
 
-    fn block(&mut self, v: &mut Vec<Stmt>) {
+    // Parses statements until a block terminator. In offside mode, a body
+    // that opens with an `Indent` token is closed by the matching `Dedent`
+    // instead of an explicit keyword; the return value tells the caller
+    // which happened, so it knows whether to still require `end`/`catch`/
+    // etc. A nested statement that closed a deeper block via `Dedent`
+    // leaves `self.tok` sitting on this block's own `Dedent`, or even
+    // straight past it on the next real token, since that statement's own
+    // `block` call already consumed its matching `Dedent` in full; either
+    // way there's no separate newline left to require here.
+    fn block(&mut self, v: &mut Vec<Stmt>) -> Result<bool, ParseError> {
+        let indented = self.eat(Tok::Indent)?;
         while !self.block_end() {
-            self.stmt(v);
-            self.require(Tok::Newline, "newline");
+            self.stmt(v)?;
+            if self.tok != Tok::Newline && self.offside {
+                continue;
+            }
+            self.require_newline_recovering()?;
         }
+        if indented {
+            self.eat(Tok::Dedent)?;
+        }
+        Ok(indented)
     }
 
-    fn parse(&mut self) -> Vec<Stmt> {
+    // Requires the explicit 'end' keyword, unless `indented` says the
+    // block was already closed by a matching `Dedent` (offside style)
+    fn end_block(&mut self, indented: bool) -> Result<(), ParseError> {
+        if indented {
+            return Ok(());
+        }
+        self.require(Tok::End, "'end'")
+    }
+
+    fn parse(&mut self) -> Result<Vec<Stmt>, ParseError> {
         // Start the tokenizer
-        self.lex();
-        self.eat(Tok::Newline);
+        if let Err(e) = self.lex() {
+            if !self.recovering {
+                return Err(e);
+            }
+            self.record_error(e);
+        }
+        self.eat(Tok::Newline)?;
 
         // Parse
         let mut v = Vec::<Stmt>::new();
-        self.block(&mut v);
+        self.block(&mut v)?;
 
         // Check for extra 'end' etc
         if self.tok != Tok::Eof {
-            self.err("Unmatched terminator");
+            let e = self.err(ParseErrorKind::UnmatchedTerminator, "Unmatched terminator");
+            if !self.recovering {
+                return Err(e);
+            }
+            self.errors.push(e);
         }
 
-        v
+        Ok(v)
+    }
+}
+
+// The library entry point: reads and parses `file` in recovery mode,
+// returning every diagnostic collected along the way instead of aborting
+// the process or stopping at the first one, so embedders (an editor, an
+// LSP, a test harness) can recover and decide what to do
+pub fn parse(file: &str) -> Result<Vec<Stmt>, Vec<ParseError>> {
+    let (v, errors) = parse_recovering(file);
+    if errors.is_empty() {
+        Ok(v)
+    } else {
+        Err(errors)
+    }
+}
+
+// Like `parse`, but for text already in memory (a REPL line, a snippet
+// passed by an embedder) rather than a file on disk; `name` is used as
+// the file field of any `Src`/`ParseError` so diagnostics still have
+// something to point at
+pub fn parse_str(name: &str, text: &str) -> Result<Vec<Stmt>, Vec<ParseError>> {
+    let (v, errors) = text_recovering(name, text);
+    if errors.is_empty() {
+        Ok(v)
+    } else {
+        Err(errors)
+    }
+}
+
+// Drives `lex()` to completion and returns every token it produces, each
+// paired with its start and end char offsets, for a caller (an editor, a
+// formatter, a syntax highlighter) that wants Verbena's tokens without
+// reimplementing the lexer. Stops at the first lex error instead of
+// recovering, since there's no AST here for a placeholder to stand in for.
+pub fn tokenize(file: &str, text: &str) -> Result<Vec<(usize, Tok, usize)>, ParseError> {
+    let text: Vec<char> = text.chars().collect();
+    let mut parser = Parser::new(file.to_string(), text);
+    let mut out = Vec::new();
+    loop {
+        parser.lex()?;
+        let src = &parser.tok_src;
+        out.push((src.start_offset, parser.tok.clone(), src.end_offset));
+        if parser.tok == Tok::Eof {
+            return Ok(out);
+        }
     }
 }
 
-pub fn parse(file: &str) -> Vec<Stmt> {
-    let mut text = match fs::read_to_string(file) {
-        Ok(a) => a,
+// Like `parse`, but instead of stopping at the first syntax error,
+// collects every diagnostic it can find in one pass: `Expr::Error`/
+// `Stmt::Error` placeholders stand in for whatever couldn't be parsed, so
+// a caller (an LSP, a test harness) can report everything wrong at once
+// instead of making the user fix mistakes one at a time
+pub fn parse_recovering(file: &str) -> (Vec<Stmt>, Vec<ParseError>) {
+    let text = match fs::read_to_string(file) {
+        Ok(text) => text,
         Err(e) => {
-            // A parser library would need to return an error result
-            // As this is a program rather than a library, we can promptly exit
-            eprintln!("{}: {}", file, e);
-            process::exit(1);
+            let err = ParseError {
+                src: Src {
+                    file: file.to_string(),
+                    line: 0,
+                    col: 0,
+                    start_offset: 0,
+                    end_offset: 0,
+                },
+                kind: ParseErrorKind::Lex,
+                message: e.to_string(),
+            };
+            return (Vec::new(), vec![err]);
         }
     };
 
-    // Check if text ends with a newline, and add one if it doesn't
+    text_recovering(file, &text)
+}
+
+// Like `parse_str`, but collecting every diagnostic instead of stopping at
+// the first one; shared by `parse_recovering` (file on disk) and
+// `parse_str` (text already in memory, e.g. a REPL line)
+fn text_recovering(name: &str, text: &str) -> (Vec<Stmt>, Vec<ParseError>) {
+    let mut text = text.to_string();
     if !text.ends_with('\n') {
         text.push('\n');
     }
 
     let text: Vec<char> = text.chars().collect();
-    let mut parser = Parser::new(file.to_string(), text);
-    parser.parse()
+    let mut parser = Parser::new(name.to_string(), text);
+    parser.recovering = true;
+    let v = match parser.parse() {
+        Ok(v) => v,
+        Err(e) => {
+            parser.errors.push(e);
+            Vec::new()
+        }
+    };
+    (v, parser.errors)
+}
+
+// The previous CLI behavior, kept as a thin wrapper so the command-line
+// program doesn't have to handle a Result: print the error and exit
+pub fn parse_or_exit(file: &str) -> Vec<Stmt> {
+    match parse(file) {
+        Ok(v) => v,
+        Err(errors) => {
+            for e in &errors {
+                eprintln!("{}", e);
+            }
+            process::exit(1);
+        }
+    }
 }
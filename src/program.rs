@@ -1,5 +1,10 @@
 use crate::ErrorContext;
 use crate::val::*;
+use num_bigint::BigInt;
+use num_complex::Complex64;
+use num_rational::BigRational;
+use num_traits::Zero;
+use std::collections::HashMap;
 
 #[derive(Debug)]
 pub enum Inst {
@@ -9,6 +14,19 @@ pub enum Inst {
     Store(String),
     StoreAt(ErrorContext),
 
+    // Object/map support: build a literal Object from n key/value pairs
+    // popped off the stack (in source order), then read or write a single
+    // field of an existing Object by key
+    MakeObject(usize),
+    GetField(ErrorContext),
+    SetField(ErrorContext),
+
+    // Emitted by the slot-resolution pass in place of Load/Store for names
+    // that resolve to a local or parameter, indexing straight into the
+    // current call frame's Vec<Val> instead of hashing a name
+    LoadSlot(usize),
+    StoreSlot(usize),
+
     Br(usize),
     BrTrue(usize),
     BrFalse(usize),
@@ -17,6 +35,13 @@ pub enum Inst {
     Return,
     Exit,
 
+    // try/catch: PushHandler marks the start of a protected region, naming
+    // where to jump and which variable to bind the caught value to if
+    // anything inside throws; PopHandler marks leaving it normally.
+    PushHandler(usize, String),
+    PopHandler,
+    Throw,
+
     Add,
     Sub(ErrorContext),
     Mul(ErrorContext),
@@ -42,6 +67,15 @@ pub enum Inst {
     Assert(ErrorContext, String),
     Call(ErrorContext, String, usize),
     CallIndirect(ErrorContext, usize),
+
+    // Builds a first-class function value: pops `free.len()` already-
+    // evaluated values off the stack (pushed by the preceding Load/LoadSlot
+    // instructions in the same order as `free`) as the closure's captured
+    // variables, and pushes a Val::Closure combining them with `params`
+    // (named for arity errors and frame layout), the entry point `pc`, and
+    // the callee's frame size `slots`. `CallIndirect` then dispatches on the
+    // resulting Val like any other closure.
+    MakeClosure(usize, usize, Vec<String>, Vec<String>),
 }
 
 pub struct Program {
@@ -63,3 +97,610 @@ impl std::fmt::Debug for Program {
         Ok(())
     }
 }
+
+// Magic bytes + format version at the start of a serialized Program, so a
+// stale or unrelated file is rejected up front instead of producing a
+// confusing decode error partway through.
+const MAGIC: &[u8; 4] = b"VBP1";
+const VERSION: u8 = 1;
+
+fn write_u32(buf: &mut Vec<u8>, n: u32) {
+    buf.extend_from_slice(&n.to_le_bytes());
+}
+
+fn read_u32(buf: &[u8], pos: &mut usize) -> Result<u32, String> {
+    if *pos + 4 > buf.len() {
+        return Err("Truncated bytecode".to_string());
+    }
+    let n = u32::from_le_bytes(buf[*pos..*pos + 4].try_into().unwrap());
+    *pos += 4;
+    Ok(n)
+}
+
+// LEB128 unsigned varint, used for the operands that show up on almost
+// every instruction (branch targets, slot/pool indices, arg counts) so
+// they cost a byte or two instead of a fixed 4-byte word each
+fn write_varint(buf: &mut Vec<u8>, mut n: u64) {
+    loop {
+        let byte = (n & 0x7f) as u8;
+        n >>= 7;
+        if n == 0 {
+            buf.push(byte);
+            break;
+        }
+        buf.push(byte | 0x80);
+    }
+}
+
+fn read_varint(buf: &[u8], pos: &mut usize) -> Result<u64, String> {
+    let mut result: u64 = 0;
+    let mut shift = 0;
+    loop {
+        if *pos >= buf.len() {
+            return Err("Truncated bytecode".to_string());
+        }
+        let byte = buf[*pos];
+        *pos += 1;
+        result |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+        if shift >= 64 {
+            return Err("Malformed varint".to_string());
+        }
+    }
+    Ok(result)
+}
+
+fn write_varint_usize(buf: &mut Vec<u8>, n: usize) {
+    write_varint(buf, n as u64);
+}
+
+fn read_varint_usize(buf: &[u8], pos: &mut usize) -> Result<usize, String> {
+    let n = read_varint(buf, pos)?;
+    n.try_into().map_err(|_| "Value out of range".to_string())
+}
+
+fn write_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    write_varint_usize(buf, bytes.len());
+    buf.extend_from_slice(bytes);
+}
+
+fn read_bytes(buf: &[u8], pos: &mut usize) -> Result<Vec<u8>, String> {
+    let n = read_varint_usize(buf, pos)?;
+    if *pos + n > buf.len() {
+        return Err("Truncated bytecode".to_string());
+    }
+    let r = buf[*pos..*pos + n].to_vec();
+    *pos += n;
+    Ok(r)
+}
+
+fn write_str(buf: &mut Vec<u8>, s: &str) {
+    write_bytes(buf, s.as_bytes());
+}
+
+fn read_str(buf: &[u8], pos: &mut usize) -> Result<String, String> {
+    let bytes = read_bytes(buf, pos)?;
+    String::from_utf8(bytes).map_err(|e| e.to_string())
+}
+
+// Collects every identifier and file path referenced by a Program's
+// instructions into a single deduplicated table, so a name repeated across
+// many instructions (a loop variable, the source file on every
+// ErrorContext) is written once instead of once per use.
+struct StringPool {
+    strings: Vec<String>,
+    index: HashMap<String, u32>,
+}
+
+impl StringPool {
+    fn new() -> Self {
+        StringPool { strings: Vec::new(), index: HashMap::new() }
+    }
+
+    fn intern(&mut self, s: &str) -> u32 {
+        if let Some(&i) = self.index.get(s) {
+            return i;
+        }
+        let i = self.strings.len() as u32;
+        self.strings.push(s.to_string());
+        self.index.insert(s.to_string(), i);
+        i
+    }
+}
+
+// Collects the distinct Val payloads carried by Inst::Const, the same way
+// StringPool collects names: a literal reused across the program (0, "",
+// ...) is written once. Only value-semantics Vals can round-trip through
+// bytecode; closures and other reference-semantics values are runtime-only.
+struct ValPool {
+    vals: Vec<Val>,
+}
+
+impl ValPool {
+    fn new() -> Self {
+        ValPool { vals: Vec::new() }
+    }
+
+    fn intern(&mut self, v: &Val) -> Result<u32, String> {
+        match v {
+            Val::True | Val::False | Val::Null | Val::Int(_) | Val::Ratio(_) | Val::Num(_) | Val::Complex(_) | Val::Str(_) => {}
+            _ => return Err("Value is not serializable".to_string()),
+        }
+        for (i, existing) in self.vals.iter().enumerate() {
+            if existing == v {
+                return Ok(i as u32);
+            }
+        }
+        let i = self.vals.len() as u32;
+        self.vals.push(v.clone());
+        Ok(i)
+    }
+}
+
+fn write_val(buf: &mut Vec<u8>, a: &Val) -> Result<(), String> {
+    match a {
+        Val::True => buf.push(0),
+        Val::False => buf.push(1),
+        Val::Null => buf.push(2),
+        Val::Int(a) => {
+            buf.push(3);
+            write_bytes(buf, &a.to_signed_bytes_le());
+        }
+        Val::Ratio(a) => {
+            buf.push(4);
+            write_bytes(buf, &a.numer().to_signed_bytes_le());
+            write_bytes(buf, &a.denom().to_signed_bytes_le());
+        }
+        Val::Num(a) => {
+            buf.push(5);
+            buf.extend_from_slice(&a.to_le_bytes());
+        }
+        Val::Complex(a) => {
+            buf.push(6);
+            buf.extend_from_slice(&a.re.to_le_bytes());
+            buf.extend_from_slice(&a.im.to_le_bytes());
+        }
+        Val::Str(s) => {
+            buf.push(7);
+            write_str(buf, s);
+        }
+        _ => return Err("Value is not serializable".to_string()),
+    }
+    Ok(())
+}
+
+fn read_val(buf: &[u8], pos: &mut usize) -> Result<Val, String> {
+    if *pos >= buf.len() {
+        return Err("Truncated bytecode".to_string());
+    }
+    let tag = buf[*pos];
+    *pos += 1;
+    let r = match tag {
+        0 => Val::True,
+        1 => Val::False,
+        2 => Val::Null,
+        3 => Val::Int(BigInt::from_signed_bytes_le(&read_bytes(buf, pos)?)),
+        4 => {
+            let numer = BigInt::from_signed_bytes_le(&read_bytes(buf, pos)?);
+            let denom = BigInt::from_signed_bytes_le(&read_bytes(buf, pos)?);
+            if denom.is_zero() {
+                return Err("Invalid ratio: zero denominator".to_string());
+            }
+            Val::ratio(BigRational::new(numer, denom))
+        }
+        5 => {
+            if *pos + 8 > buf.len() {
+                return Err("Truncated bytecode".to_string());
+            }
+            let a = f64::from_le_bytes(buf[*pos..*pos + 8].try_into().unwrap());
+            *pos += 8;
+            Val::Num(a)
+        }
+        6 => {
+            if *pos + 16 > buf.len() {
+                return Err("Truncated bytecode".to_string());
+            }
+            let re = f64::from_le_bytes(buf[*pos..*pos + 8].try_into().unwrap());
+            let im = f64::from_le_bytes(buf[*pos + 8..*pos + 16].try_into().unwrap());
+            *pos += 16;
+            Val::Complex(Complex64::new(re, im))
+        }
+        7 => Val::Str(read_str(buf, pos)?),
+        _ => return Err(format!("Unknown constant tag {}", tag)),
+    };
+    Ok(r)
+}
+
+fn write_ec(buf: &mut Vec<u8>, ec: &ErrorContext, strings: &mut StringPool) {
+    write_varint_usize(buf, strings.intern(&ec.file) as usize);
+    write_varint_usize(buf, ec.line);
+}
+
+fn read_ec(buf: &[u8], pos: &mut usize, strings: &[String]) -> Result<ErrorContext, String> {
+    let file_idx = read_varint_usize(buf, pos)?;
+    let file = strings.get(file_idx).ok_or("String index out of range")?.clone();
+    let line = read_varint_usize(buf, pos)?;
+    Ok(ErrorContext { file, line })
+}
+
+fn write_inst(buf: &mut Vec<u8>, a: &Inst, strings: &mut StringPool, consts: &mut ValPool) -> Result<(), String> {
+    match a {
+        Inst::Const(v) => {
+            buf.push(0);
+            write_varint_usize(buf, consts.intern(v)? as usize);
+        }
+        Inst::Pop => buf.push(1),
+        Inst::Load(ec, name) => {
+            buf.push(2);
+            write_ec(buf, ec, strings);
+            write_varint_usize(buf, strings.intern(name) as usize);
+        }
+        Inst::Store(name) => {
+            buf.push(3);
+            write_varint_usize(buf, strings.intern(name) as usize);
+        }
+        Inst::StoreAt(ec) => {
+            buf.push(4);
+            write_ec(buf, ec, strings);
+        }
+        Inst::LoadSlot(i) => {
+            buf.push(5);
+            write_varint_usize(buf, *i);
+        }
+        Inst::StoreSlot(i) => {
+            buf.push(6);
+            write_varint_usize(buf, *i);
+        }
+        Inst::Br(i) => {
+            buf.push(7);
+            write_varint_usize(buf, *i);
+        }
+        Inst::BrTrue(i) => {
+            buf.push(8);
+            write_varint_usize(buf, *i);
+        }
+        Inst::BrFalse(i) => {
+            buf.push(9);
+            write_varint_usize(buf, *i);
+        }
+        Inst::DupBrTrue(i) => {
+            buf.push(10);
+            write_varint_usize(buf, *i);
+        }
+        Inst::DupBrFalse(i) => {
+            buf.push(11);
+            write_varint_usize(buf, *i);
+        }
+        Inst::Return => buf.push(12),
+        Inst::Exit => buf.push(13),
+        Inst::PushHandler(target, name) => {
+            buf.push(14);
+            write_varint_usize(buf, *target);
+            write_varint_usize(buf, strings.intern(name) as usize);
+        }
+        Inst::PopHandler => buf.push(15),
+        Inst::Throw => buf.push(16),
+        Inst::Add => buf.push(17),
+        Inst::Sub(ec) => {
+            buf.push(18);
+            write_ec(buf, ec, strings);
+        }
+        Inst::Mul(ec) => {
+            buf.push(19);
+            write_ec(buf, ec, strings);
+        }
+        Inst::IDiv(ec) => {
+            buf.push(20);
+            write_ec(buf, ec, strings);
+        }
+        Inst::FDiv(ec) => {
+            buf.push(21);
+            write_ec(buf, ec, strings);
+        }
+        Inst::Mod(ec) => {
+            buf.push(22);
+            write_ec(buf, ec, strings);
+        }
+        Inst::Shl(ec) => {
+            buf.push(23);
+            write_ec(buf, ec, strings);
+        }
+        Inst::Shr(ec) => {
+            buf.push(24);
+            write_ec(buf, ec, strings);
+        }
+        Inst::BitAnd(ec) => {
+            buf.push(25);
+            write_ec(buf, ec, strings);
+        }
+        Inst::BitOr(ec) => {
+            buf.push(26);
+            write_ec(buf, ec, strings);
+        }
+        Inst::BitXor(ec) => {
+            buf.push(27);
+            write_ec(buf, ec, strings);
+        }
+        Inst::BitNot(ec) => {
+            buf.push(28);
+            write_ec(buf, ec, strings);
+        }
+        Inst::Neg(ec) => {
+            buf.push(29);
+            write_ec(buf, ec, strings);
+        }
+        Inst::Not => buf.push(30),
+        Inst::Eq => buf.push(31),
+        Inst::Ne => buf.push(32),
+        Inst::Lt => buf.push(33),
+        Inst::Gt => buf.push(34),
+        Inst::Le => buf.push(35),
+        Inst::Ge => buf.push(36),
+        Inst::Pow(ec) => {
+            buf.push(37);
+            write_ec(buf, ec, strings);
+        }
+        Inst::Assert(ec, msg) => {
+            buf.push(38);
+            write_ec(buf, ec, strings);
+            write_varint_usize(buf, strings.intern(msg) as usize);
+        }
+        Inst::Call(ec, name, argc) => {
+            buf.push(39);
+            write_ec(buf, ec, strings);
+            write_varint_usize(buf, strings.intern(name) as usize);
+            write_varint_usize(buf, *argc);
+        }
+        Inst::CallIndirect(ec, argc) => {
+            buf.push(40);
+            write_ec(buf, ec, strings);
+            write_varint_usize(buf, *argc);
+        }
+        Inst::MakeObject(n) => {
+            buf.push(41);
+            write_varint_usize(buf, *n);
+        }
+        Inst::GetField(ec) => {
+            buf.push(42);
+            write_ec(buf, ec, strings);
+        }
+        Inst::SetField(ec) => {
+            buf.push(43);
+            write_ec(buf, ec, strings);
+        }
+        Inst::MakeClosure(pc, slots, params, free) => {
+            buf.push(44);
+            write_varint_usize(buf, *pc);
+            write_varint_usize(buf, *slots);
+            write_varint_usize(buf, params.len());
+            for p in params {
+                write_varint_usize(buf, strings.intern(p) as usize);
+            }
+            write_varint_usize(buf, free.len());
+            for name in free {
+                write_varint_usize(buf, strings.intern(name) as usize);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_inst(buf: &[u8], pos: &mut usize, strings: &[String], consts: &[Val]) -> Result<Inst, String> {
+    if *pos >= buf.len() {
+        return Err("Truncated bytecode".to_string());
+    }
+    let tag = buf[*pos];
+    *pos += 1;
+    let r = match tag {
+        0 => {
+            let i = read_varint_usize(buf, pos)?;
+            Inst::Const(consts.get(i).cloned().ok_or("Constant index out of range")?)
+        }
+        1 => Inst::Pop,
+        2 => {
+            let ec = read_ec(buf, pos, strings)?;
+            let i = read_varint_usize(buf, pos)?;
+            let name = strings.get(i).ok_or("String index out of range")?.clone();
+            Inst::Load(ec, name)
+        }
+        3 => {
+            let i = read_varint_usize(buf, pos)?;
+            Inst::Store(strings.get(i).ok_or("String index out of range")?.clone())
+        }
+        4 => Inst::StoreAt(read_ec(buf, pos, strings)?),
+        5 => Inst::LoadSlot(read_varint_usize(buf, pos)?),
+        6 => Inst::StoreSlot(read_varint_usize(buf, pos)?),
+        7 => Inst::Br(read_varint_usize(buf, pos)?),
+        8 => Inst::BrTrue(read_varint_usize(buf, pos)?),
+        9 => Inst::BrFalse(read_varint_usize(buf, pos)?),
+        10 => Inst::DupBrTrue(read_varint_usize(buf, pos)?),
+        11 => Inst::DupBrFalse(read_varint_usize(buf, pos)?),
+        12 => Inst::Return,
+        13 => Inst::Exit,
+        14 => {
+            let target = read_varint_usize(buf, pos)?;
+            let i = read_varint_usize(buf, pos)?;
+            let name = strings.get(i).ok_or("String index out of range")?.clone();
+            Inst::PushHandler(target, name)
+        }
+        15 => Inst::PopHandler,
+        16 => Inst::Throw,
+        17 => Inst::Add,
+        18 => Inst::Sub(read_ec(buf, pos, strings)?),
+        19 => Inst::Mul(read_ec(buf, pos, strings)?),
+        20 => Inst::IDiv(read_ec(buf, pos, strings)?),
+        21 => Inst::FDiv(read_ec(buf, pos, strings)?),
+        22 => Inst::Mod(read_ec(buf, pos, strings)?),
+        23 => Inst::Shl(read_ec(buf, pos, strings)?),
+        24 => Inst::Shr(read_ec(buf, pos, strings)?),
+        25 => Inst::BitAnd(read_ec(buf, pos, strings)?),
+        26 => Inst::BitOr(read_ec(buf, pos, strings)?),
+        27 => Inst::BitXor(read_ec(buf, pos, strings)?),
+        28 => Inst::BitNot(read_ec(buf, pos, strings)?),
+        29 => Inst::Neg(read_ec(buf, pos, strings)?),
+        30 => Inst::Not,
+        31 => Inst::Eq,
+        32 => Inst::Ne,
+        33 => Inst::Lt,
+        34 => Inst::Gt,
+        35 => Inst::Le,
+        36 => Inst::Ge,
+        37 => Inst::Pow(read_ec(buf, pos, strings)?),
+        38 => {
+            let ec = read_ec(buf, pos, strings)?;
+            let i = read_varint_usize(buf, pos)?;
+            let msg = strings.get(i).ok_or("String index out of range")?.clone();
+            Inst::Assert(ec, msg)
+        }
+        39 => {
+            let ec = read_ec(buf, pos, strings)?;
+            let i = read_varint_usize(buf, pos)?;
+            let name = strings.get(i).ok_or("String index out of range")?.clone();
+            let argc = read_varint_usize(buf, pos)?;
+            Inst::Call(ec, name, argc)
+        }
+        40 => {
+            let ec = read_ec(buf, pos, strings)?;
+            let argc = read_varint_usize(buf, pos)?;
+            Inst::CallIndirect(ec, argc)
+        }
+        41 => Inst::MakeObject(read_varint_usize(buf, pos)?),
+        42 => Inst::GetField(read_ec(buf, pos, strings)?),
+        43 => Inst::SetField(read_ec(buf, pos, strings)?),
+        44 => {
+            let pc = read_varint_usize(buf, pos)?;
+            let slots = read_varint_usize(buf, pos)?;
+            let n = read_varint_usize(buf, pos)?;
+            let mut params = Vec::with_capacity(n.min(1024));
+            for _ in 0..n {
+                let i = read_varint_usize(buf, pos)?;
+                params.push(strings.get(i).ok_or("String index out of range")?.clone());
+            }
+            let n = read_varint_usize(buf, pos)?;
+            let mut free = Vec::with_capacity(n.min(1024));
+            for _ in 0..n {
+                let i = read_varint_usize(buf, pos)?;
+                free.push(strings.get(i).ok_or("String index out of range")?.clone());
+            }
+            Inst::MakeClosure(pc, slots, params, free)
+        }
+        _ => return Err(format!("Unknown instruction tag {}", tag)),
+    };
+    Ok(r)
+}
+
+// Every branch-like operand names an offset into `code`; checked only
+// after the whole stream is decoded, since a forward branch's target
+// hasn't been read yet while decoding the instruction that names it.
+pub(crate) fn branch_target(inst: &Inst) -> Option<usize> {
+    match inst {
+        Inst::Br(i)
+        | Inst::BrTrue(i)
+        | Inst::BrFalse(i)
+        | Inst::DupBrTrue(i)
+        | Inst::DupBrFalse(i)
+        | Inst::PushHandler(i, _) => Some(*i),
+        // MakeClosure's `pc` is a code offset exactly like a branch target:
+        // it has to be retargeted by apply_rewrites when earlier code
+        // shifts, and eliminate_dead_code has to see the closure body as
+        // reachable even when nothing else jumps to it.
+        Inst::MakeClosure(pc, _, _, _) => Some(*pc),
+        _ => None,
+    }
+}
+
+// The write side of `branch_target`, used by the optimizer to retarget a
+// branch after instructions have been inserted, removed, or reordered.
+pub(crate) fn set_branch_target(inst: &mut Inst, target: usize) {
+    match inst {
+        Inst::Br(i)
+        | Inst::BrTrue(i)
+        | Inst::BrFalse(i)
+        | Inst::DupBrTrue(i)
+        | Inst::DupBrFalse(i)
+        | Inst::PushHandler(i, _) => *i = target,
+        Inst::MakeClosure(pc, _, _, _) => *pc = target,
+        _ => panic!("Not a branch instruction"),
+    }
+}
+
+impl Program {
+    // Serializes this program's bytecode to a portable byte buffer, so it
+    // can be cached on disk and reloaded without recompiling from source.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        let mut strings = StringPool::new();
+        let mut consts = ValPool::new();
+        let mut code_buf = Vec::new();
+        for inst in &self.code {
+            write_inst(&mut code_buf, inst, &mut strings, &mut consts)?;
+        }
+
+        let mut buf = Vec::new();
+        buf.extend_from_slice(MAGIC);
+        buf.push(VERSION);
+
+        write_u32(&mut buf, strings.strings.len() as u32);
+        for s in &strings.strings {
+            write_str(&mut buf, s);
+        }
+
+        write_u32(&mut buf, consts.vals.len() as u32);
+        for v in &consts.vals {
+            write_val(&mut buf, v)?;
+        }
+
+        write_u32(&mut buf, self.code.len() as u32);
+        buf.extend_from_slice(&code_buf);
+
+        Ok(buf)
+    }
+
+    // Reloads a program previously written by `to_bytes`, rejecting a
+    // truncated stream, an unknown opcode/constant tag, or a branch whose
+    // target falls outside the decoded instruction stream.
+    pub fn from_bytes(buf: &[u8]) -> Result<Self, String> {
+        if buf.len() < MAGIC.len() || &buf[..MAGIC.len()] != MAGIC {
+            return Err("Not a verbena bytecode program".to_string());
+        }
+        let mut pos = MAGIC.len();
+        if pos >= buf.len() {
+            return Err("Truncated bytecode".to_string());
+        }
+        let version = buf[pos];
+        pos += 1;
+        if version != VERSION {
+            return Err(format!("Unsupported bytecode version {}", version));
+        }
+
+        let n = read_u32(buf, &mut pos)? as usize;
+        let mut strings = Vec::with_capacity(n.min(1024));
+        for _ in 0..n {
+            strings.push(read_str(buf, &mut pos)?);
+        }
+
+        let n = read_u32(buf, &mut pos)? as usize;
+        let mut consts = Vec::with_capacity(n.min(1024));
+        for _ in 0..n {
+            consts.push(read_val(buf, &mut pos)?);
+        }
+
+        let n = read_u32(buf, &mut pos)? as usize;
+        let mut code = Vec::with_capacity(n.min(1024));
+        for _ in 0..n {
+            code.push(read_inst(buf, &mut pos, &strings, &consts)?);
+        }
+
+        for inst in &code {
+            if let Some(target) = branch_target(inst) {
+                if target >= code.len() {
+                    return Err("Branch target out of range".to_string());
+                }
+            }
+        }
+
+        Ok(Program { code })
+    }
+}
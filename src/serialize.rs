@@ -0,0 +1,136 @@
+// A compact, self-describing, length-prefixed encoding for Val, modeled on
+// netstrings: each value is a single type tag followed by a length-prefixed
+// payload, so a reader can skip or stream a value without fully decoding it.
+//
+//   u,                         null
+//   n1:1,  n1:0,                booleans
+//   i<len>:<digits>,            arbitrary-precision integer
+//   f<len>:<decimal>,           float
+//   t<len>:<utf8 bytes>,        string (len is a BYTE length, not chars)
+//   [<len>:<concatenated values>]   list (len is the byte length of the
+//                                   concatenated inner encodings)
+use crate::list::*;
+use crate::val::*;
+use num_bigint::BigInt;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::str::FromStr;
+
+pub fn encode(v: &Val) -> Result<String, String> {
+    match v {
+        Val::Null => Ok("u,".to_string()),
+        Val::True => Ok("n1:1,".to_string()),
+        Val::False => Ok("n1:0,".to_string()),
+        Val::Int(a) => {
+            let digits = a.to_string();
+            Ok(format!("i{}:{},", digits.len(), digits))
+        }
+        Val::Num(a) => {
+            let digits = a.to_string();
+            Ok(format!("f{}:{},", digits.len(), digits))
+        }
+        Val::Str(s) => Ok(format!("t{}:{},", s.len(), s)),
+        Val::List(a) => {
+            let a = a.borrow();
+            let mut inner = String::new();
+            for item in a.v.iter() {
+                inner.push_str(&encode(item)?);
+            }
+            Ok(format!("[{}:{}]", inner.len(), inner))
+        }
+        _ => Err("Cannot encode this type".to_string()),
+    }
+}
+
+fn read_len(input: &[u8]) -> Result<(usize, &[u8]), String> {
+    let pos = input
+        .iter()
+        .position(|&b| b == b':')
+        .ok_or("Truncated input: missing length delimiter")?;
+    let digits =
+        std::str::from_utf8(&input[..pos]).map_err(|_| "Invalid length digits".to_string())?;
+    let len: usize = digits
+        .parse()
+        .map_err(|_| "Invalid length digits".to_string())?;
+    Ok((len, &input[pos + 1..]))
+}
+
+fn take(input: &[u8], len: usize) -> Result<(&[u8], &[u8]), String> {
+    if input.len() < len {
+        return Err("Truncated input".to_string());
+    }
+    Ok(input.split_at(len))
+}
+
+fn expect(input: &[u8], b: u8) -> Result<&[u8], String> {
+    match input.first() {
+        Some(&c) if c == b => Ok(&input[1..]),
+        Some(_) => Err(format!("Expected '{}'", b as char)),
+        None => Err("Truncated input".to_string()),
+    }
+}
+
+fn decode_one(input: &[u8]) -> Result<(Val, &[u8]), String> {
+    let (&tag, rest) = input.split_first().ok_or("Truncated input")?;
+    match tag {
+        b'u' => Ok((Val::Null, expect(rest, b',')?)),
+        b'n' => {
+            let (len, rest) = read_len(rest)?;
+            if len != 1 {
+                return Err("Invalid boolean length".to_string());
+            }
+            let (payload, rest) = take(rest, len)?;
+            let rest = expect(rest, b',')?;
+            let v = match payload[0] {
+                b'1' => Val::True,
+                b'0' => Val::False,
+                _ => return Err("Invalid boolean payload".to_string()),
+            };
+            Ok((v, rest))
+        }
+        b'i' => {
+            let (len, rest) = read_len(rest)?;
+            let (payload, rest) = take(rest, len)?;
+            let rest = expect(rest, b',')?;
+            let s = std::str::from_utf8(payload).map_err(|e| e.to_string())?;
+            let a = BigInt::from_str(s).map_err(|e| e.to_string())?;
+            Ok((Val::Int(a), rest))
+        }
+        b'f' => {
+            let (len, rest) = read_len(rest)?;
+            let (payload, rest) = take(rest, len)?;
+            let rest = expect(rest, b',')?;
+            let s = std::str::from_utf8(payload).map_err(|e| e.to_string())?;
+            let a: f64 = s.parse().map_err(|_| "Invalid float".to_string())?;
+            Ok((Val::Num(a), rest))
+        }
+        b't' => {
+            let (len, rest) = read_len(rest)?;
+            let (payload, rest) = take(rest, len)?;
+            let rest = expect(rest, b',')?;
+            let s = String::from_utf8(payload.to_vec()).map_err(|e| e.to_string())?;
+            Ok((Val::Str(s), rest))
+        }
+        b'[' => {
+            let (len, rest) = read_len(rest)?;
+            let (mut inner, rest) = take(rest, len)?;
+            let rest = expect(rest, b']')?;
+            let mut items = Vec::new();
+            while !inner.is_empty() {
+                let (v, tail) = decode_one(inner)?;
+                items.push(v);
+                inner = tail;
+            }
+            Ok((Val::List(Rc::new(RefCell::new(List::from(items)))), rest))
+        }
+        _ => Err(format!("Unknown type tag: '{}'", tag as char)),
+    }
+}
+
+pub fn decode(s: &str) -> Result<Val, String> {
+    let (v, rest) = decode_one(s.as_bytes())?;
+    if !rest.is_empty() {
+        return Err("Trailing data after encoded value".to_string());
+    }
+    Ok(v)
+}
@@ -1,10 +1,16 @@
 use crate::list::*;
 use crate::val::*;
 use crate::vm::*;
+use base64::Engine;
 use num_bigint::BigInt;
+use num_complex::Complex64;
+use num_rational::BigRational;
+use num_traits::One;
 use num_traits::ToPrimitive;
 use num_traits::Zero;
 use rand::Rng;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
 use std::cell::RefCell;
 use std::io;
 use std::io::Write;
@@ -29,15 +35,63 @@ fn input(_vm: &mut VM) -> Result<Val, String> {
 }
 
 fn sqrt(_vm: &mut VM, a: Val) -> Result<Val, String> {
+    if let Val::Complex(a) = a {
+        return Ok(Val::Complex(a.sqrt()));
+    }
     let a = a.num()?;
     let r = match a {
+        Val::Num(a) if a < 0.0 => Val::Complex(Complex64::new(a, 0.0).sqrt()),
         Val::Num(a) => Val::Num(a.sqrt()),
+        Val::Int(a) if a < BigInt::zero() => {
+            Val::Complex(Complex64::new(a.to_f64().unwrap_or(f64::NAN), 0.0).sqrt())
+        }
         Val::Int(a) => Val::Int(a.sqrt()),
         _ => panic!(),
     };
     Ok(r)
 }
 
+fn complex(_vm: &mut VM, re: Val, im: Val) -> Result<Val, String> {
+    let re = re.to_f64()?;
+    let im = im.to_f64()?;
+    Ok(Val::complex(re, im))
+}
+
+fn re(_vm: &mut VM, a: Val) -> Result<Val, String> {
+    match a {
+        Val::Complex(a) => Ok(Val::Num(a.re)),
+        _ => Err("Not a complex number".to_string()),
+    }
+}
+
+fn im(_vm: &mut VM, a: Val) -> Result<Val, String> {
+    match a {
+        Val::Complex(a) => Ok(Val::Num(a.im)),
+        _ => Err("Not a complex number".to_string()),
+    }
+}
+
+fn conj(_vm: &mut VM, a: Val) -> Result<Val, String> {
+    match a {
+        Val::Complex(a) => Ok(Val::Complex(a.conj())),
+        _ => Err("Not a complex number".to_string()),
+    }
+}
+
+fn arg(_vm: &mut VM, a: Val) -> Result<Val, String> {
+    match a {
+        Val::Complex(a) => Ok(Val::Num(a.arg())),
+        _ => Err("Not a complex number".to_string()),
+    }
+}
+
+fn modulus(_vm: &mut VM, a: Val) -> Result<Val, String> {
+    match a {
+        Val::Complex(a) => Ok(Val::Num(a.norm())),
+        _ => Err("Not a complex number".to_string()),
+    }
+}
+
 fn num(_vm: &mut VM, a: Val) -> Result<Val, String> {
     let r = match a {
         Val::True => 1.0,
@@ -54,6 +108,19 @@ fn num(_vm: &mut VM, a: Val) -> Result<Val, String> {
     Ok(r)
 }
 
+// Reports which rung of the Int/Ratio/Num tower `a` occupies, so scripts can
+// tell an exact fraction from a float without relying on `typeof`'s generic
+// "ratio"/"num" spelling
+fn number_type(_vm: &mut VM, a: Val) -> Result<Val, String> {
+    let r = match a.num()? {
+        Val::Int(_) => "int",
+        Val::Ratio(_) => "ratio",
+        Val::Num(_) => "float",
+        _ => unreachable!(),
+    };
+    Ok(Val::Str(r.to_string()))
+}
+
 fn str_(_vm: &mut VM, a: Val) -> Result<Val, String> {
     let r = Val::Str(a.to_string());
     Ok(r)
@@ -68,9 +135,14 @@ fn _print(_vm: &mut VM, a: Val) -> Result<Val, String> {
 fn typeof_(_vm: &mut VM, a: Val) -> Result<Val, String> {
     let r = match a {
         Val::Int(_) => "int",
+        Val::Ratio(_) => "ratio",
         Val::Num(_) => "num",
+        Val::Complex(_) => "complex",
         Val::Str(_) => "str",
         Val::List(_) => "list",
+        Val::Object(_) => "object",
+        Val::Bytes(_) => "bytes",
+        Val::Iter(_) => "iter",
         Val::True | Val::False => "bool",
         Val::Null => "null",
         _ => "fn",
@@ -80,6 +152,110 @@ fn typeof_(_vm: &mut VM, a: Val) -> Result<Val, String> {
     Ok(r)
 }
 
+// Lazily yields `start, start+step, start+2*step, ...`, stopping once the
+// running value reaches `stop` (or never, if `stop` is Null) — the
+// direction is inferred from the sign of `step`
+fn range(_vm: &mut VM, start: Val, stop: Val, step: Val) -> Result<Val, String> {
+    let mut cur = start.to_bigint()?;
+    let step = step.to_bigint()?;
+    if step.is_zero() {
+        return Err("Step must not be zero".to_string());
+    }
+    let ascending = step > BigInt::zero();
+    let stop = match stop {
+        Val::Null => None,
+        _ => Some(stop.to_bigint()?),
+    };
+    Ok(Val::Iter(Rc::new(RefCell::new(move |_vm: &mut VM| {
+        if let Some(stop) = &stop {
+            if (ascending && cur >= *stop) || (!ascending && cur <= *stop) {
+                return Ok(None);
+            }
+        }
+        let r = Val::Int(cur.clone());
+        cur += &step;
+        Ok(Some(r))
+    }))))
+}
+
+// Applies `f` to each element pulled from `it`, lazily: `f` only runs once
+// the consumer (a for-loop, `collect`, `reduce`, ...) actually pulls a value
+fn map(_vm: &mut VM, f: Val, it: Val) -> Result<Val, String> {
+    let it = to_iter(it)?;
+    Ok(Val::Iter(Rc::new(RefCell::new(move |vm: &mut VM| {
+        match next(vm, &it)? {
+            Some(x) => Ok(Some(vm.call_value(&f, vec![x])?)),
+            None => Ok(None),
+        }
+    }))))
+}
+
+// Lazily yields only the elements of `it` for which `f` is truthy
+fn filter(_vm: &mut VM, f: Val, it: Val) -> Result<Val, String> {
+    let it = to_iter(it)?;
+    Ok(Val::Iter(Rc::new(RefCell::new(move |vm: &mut VM| {
+        while let Some(x) = next(vm, &it)? {
+            if vm.call_value(&f, vec![x.clone()])?.truth() {
+                return Ok(Some(x));
+            }
+        }
+        Ok(None)
+    }))))
+}
+
+// Lazily yields at most the first `n` elements of `it`
+fn take(_vm: &mut VM, n: Val, it: Val) -> Result<Val, String> {
+    let mut remaining = n.to_usize()?;
+    let it = to_iter(it)?;
+    Ok(Val::Iter(Rc::new(RefCell::new(move |vm: &mut VM| {
+        if remaining == 0 {
+            return Ok(None);
+        }
+        remaining -= 1;
+        next(vm, &it)
+    }))))
+}
+
+// Lazily pairs up elements from `a` and `b`, stopping as soon as either runs out
+fn zip(_vm: &mut VM, a: Val, b: Val) -> Result<Val, String> {
+    let a = to_iter(a)?;
+    let b = to_iter(b)?;
+    Ok(Val::Iter(Rc::new(RefCell::new(move |vm: &mut VM| {
+        let x = match next(vm, &a)? {
+            Some(x) => x,
+            None => return Ok(None),
+        };
+        let y = match next(vm, &b)? {
+            Some(y) => y,
+            None => return Ok(None),
+        };
+        let pair = List::from(vec![x, y]);
+        Ok(Some(Val::List(Rc::new(RefCell::new(pair)))))
+    }))))
+}
+
+// Folds `it` down to a single value by repeatedly calling `f(acc, x)`,
+// starting from `init`. Unlike the lazy builtins above, this drains `it`
+// immediately
+fn reduce(vm: &mut VM, f: Val, init: Val, it: Val) -> Result<Val, String> {
+    let it = to_iter(it)?;
+    let mut acc = init;
+    while let Some(x) = next(vm, &it)? {
+        acc = vm.call_value(&f, vec![acc, x])?;
+    }
+    Ok(acc)
+}
+
+// Drains `it` into a `Val::List`, forcing a lazy pipeline's results
+fn collect(vm: &mut VM, it: Val) -> Result<Val, String> {
+    let it = to_iter(it)?;
+    let mut v = Vec::new();
+    while let Some(x) = next(vm, &it)? {
+        v.push(x);
+    }
+    Ok(Val::List(Rc::new(RefCell::new(List::from(v)))))
+}
+
 fn copysign(_vm: &mut VM, a: Val, sign: Val) -> Result<Val, String> {
     let a = a.to_f64()?;
     let sign = sign.to_f64()?;
@@ -104,6 +280,96 @@ fn numbase(_vm: &mut VM, s: Val, base: Val) -> Result<Val, String> {
     Ok(r)
 }
 
+// Constructs a normalized exact fraction (Val::Ratio collapses to Val::Int
+// once the denominator reduces to 1, e.g. rat(6, 3) -> 2)
+fn rat(_vm: &mut VM, numerator: Val, denominator: Val) -> Result<Val, String> {
+    let numerator = numerator.to_bigint()?;
+    let denominator = denominator.to_bigint()?;
+    if denominator.is_zero() {
+        return Err("Division by zero".to_string());
+    }
+    Ok(Val::ratio(BigRational::new(numerator, denominator)))
+}
+
+fn numer(_vm: &mut VM, a: Val) -> Result<Val, String> {
+    let r = match a.num()? {
+        Val::Int(a) => a,
+        Val::Ratio(a) => a.numer().clone(),
+        _ => return Err("Not an exact number".to_string()),
+    };
+    Ok(Val::Int(r))
+}
+
+fn denom(_vm: &mut VM, a: Val) -> Result<Val, String> {
+    let r = match a.num()? {
+        Val::Int(_) => BigInt::one(),
+        Val::Ratio(a) => a.denom().clone(),
+        _ => return Err("Not an exact number".to_string()),
+    };
+    Ok(Val::Int(r))
+}
+
+// Like `strbase`, but validates the base and propagates the error instead of
+// leaving out-of-range bases to panic inside BigInt::to_str_radix
+fn to_radix(_vm: &mut VM, a: Val, base: Val) -> Result<Val, String> {
+    let base = base.to_u32()?;
+    let r = a.to_radix(base)?;
+    Ok(Val::Str(r))
+}
+
+// Like `numbase`, but validates the base and tolerates whitespace,
+// underscores, and a leading sign the way numeric literals do
+fn parse_radix(_vm: &mut VM, s: Val, base: Val) -> Result<Val, String> {
+    let s = s.as_string()?;
+    let base = base.to_u32()?;
+    Val::parse_radix(&s, base)
+}
+
+// Renders `x` in scientific notation as `m.mmmeSNN`, normalizing the
+// mantissa into [1, 10) and writing the exponent with an explicit sign and
+// at least two digits. Rounding a mantissa like 9.9995 up to 10 at the
+// requested precision would break that invariant, so it's renormalized
+// afterward.
+fn format_exp(x: f64, precision: usize) -> String {
+    if x == 0.0 {
+        return format!("{:.*}e+00", precision, 0.0);
+    }
+    let sign = if x < 0.0 { "-" } else { "" };
+    let x = x.abs();
+    let exp = x.log10().floor() as i32;
+    let mantissa = x / 10f64.powi(exp);
+    let mantissa_str = format!("{:.*}", precision, mantissa);
+    let (mantissa_str, exp) = if mantissa_str.starts_with("10") {
+        (format!("{:.*}", precision, mantissa / 10.0), exp + 1)
+    } else {
+        (mantissa_str, exp)
+    };
+    format!("{}{}e{}{:02}", sign, mantissa_str, if exp < 0 { "-" } else { "+" }, exp.abs())
+}
+
+// Gives scripts control over numeric output that Display's default `{}`
+// doesn't: `mode` is "fixed" (exactly `precision` digits after the point),
+// "exp" (scientific notation, `precision` mantissa digits), or "shortest"
+// (the minimal round-trippable decimal, which is what Display already does)
+fn format_num(_vm: &mut VM, x: Val, precision: Val, mode: Val) -> Result<Val, String> {
+    let x = x.to_f64()?;
+    let precision = precision.to_usize()?;
+    let mode = mode.as_string()?;
+    let r = if x.is_nan() {
+        "nan".to_string()
+    } else if x.is_infinite() {
+        if x < 0.0 { "-inf".to_string() } else { "inf".to_string() }
+    } else {
+        match mode.as_str() {
+            "fixed" => format!("{:.*}", precision, x),
+            "exp" => format_exp(x, precision),
+            "shortest" => format!("{}", x),
+            _ => return Err(format!("Unknown format mode: {}", mode)),
+        }
+    };
+    Ok(Val::Str(r))
+}
+
 fn abs(_vm: &mut VM, a: Val) -> Result<Val, String> {
     let a = a.to_f64()?;
     let r = Val::Num(a.abs());
@@ -123,11 +389,33 @@ fn _list(_vm: &mut VM, items: Vec<Val>) -> Result<Val, String> {
     Ok(r)
 }
 
+// Seconds since the Unix epoch, via the VM's clock so it can be virtualized in tests
+fn now(vm: &mut VM) -> Result<Val, String> {
+    Ok(Val::Num(vm.clock.now()))
+}
+
 fn rnd(vm: &mut VM) -> Result<Val, String> {
     let r: f64 = vm.rng.random();
     Ok(Val::Num(r))
 }
 
+// Reseeds the VM's RNG, so a script can make its own randomness reproducible
+fn reseed(vm: &mut VM, n: Val) -> Result<Val, String> {
+    let n = n.to_u64()?;
+    vm.rng = ChaCha20Rng::seed_from_u64(n);
+    Ok(Val::Null)
+}
+
+// Returns true with the given probability (0.0 to 1.0), false otherwise
+fn rndbool(vm: &mut VM, p: Val) -> Result<Val, String> {
+    let p = p.to_f64()?;
+    if !(0.0..=1.0).contains(&p) {
+        return Err("Probability must be between 0 and 1".to_string());
+    }
+    let r: f64 = vm.rng.random();
+    Ok(Val::from_bool(r < p))
+}
+
 fn floor(_vm: &mut VM, a: Val) -> Result<Val, String> {
     let a = a.to_f64()?;
     let r = Val::Num(a.floor());
@@ -167,6 +455,9 @@ fn fma(_vm: &mut VM, a: Val, b: Val, c: Val) -> Result<Val, String> {
 }
 
 fn exp(_vm: &mut VM, a: Val) -> Result<Val, String> {
+    if let Val::Complex(a) = a {
+        return Ok(Val::Complex(a.exp()));
+    }
     let a = a.to_f64()?;
     let r = Val::Num(a.exp());
     Ok(r)
@@ -179,6 +470,9 @@ fn exp2(_vm: &mut VM, a: Val) -> Result<Val, String> {
 }
 
 fn log(_vm: &mut VM, a: Val) -> Result<Val, String> {
+    if let Val::Complex(a) = a {
+        return Ok(Val::Complex(a.ln()));
+    }
     let a = a.to_f64()?;
     let r = Val::Num(a.ln());
     Ok(r)
@@ -204,36 +498,54 @@ fn hypot(_vm: &mut VM, a: Val, b: Val) -> Result<Val, String> {
 }
 
 fn sin(_vm: &mut VM, a: Val) -> Result<Val, String> {
+    if let Val::Complex(a) = a {
+        return Ok(Val::Complex(a.sin()));
+    }
     let a = a.to_f64()?;
     let r = Val::Num(a.sin());
     Ok(r)
 }
 
 fn cos(_vm: &mut VM, a: Val) -> Result<Val, String> {
+    if let Val::Complex(a) = a {
+        return Ok(Val::Complex(a.cos()));
+    }
     let a = a.to_f64()?;
     let r = Val::Num(a.cos());
     Ok(r)
 }
 
 fn tan(_vm: &mut VM, a: Val) -> Result<Val, String> {
+    if let Val::Complex(a) = a {
+        return Ok(Val::Complex(a.tan()));
+    }
     let a = a.to_f64()?;
     let r = Val::Num(a.tan());
     Ok(r)
 }
 
 fn asin(_vm: &mut VM, a: Val) -> Result<Val, String> {
+    if let Val::Complex(a) = a {
+        return Ok(Val::Complex(a.asin()));
+    }
     let a = a.to_f64()?;
     let r = Val::Num(a.asin());
     Ok(r)
 }
 
 fn acos(_vm: &mut VM, a: Val) -> Result<Val, String> {
+    if let Val::Complex(a) = a {
+        return Ok(Val::Complex(a.acos()));
+    }
     let a = a.to_f64()?;
     let r = Val::Num(a.acos());
     Ok(r)
 }
 
 fn atan(_vm: &mut VM, a: Val) -> Result<Val, String> {
+    if let Val::Complex(a) = a {
+        return Ok(Val::Complex(a.atan()));
+    }
     let a = a.to_f64()?;
     let r = Val::Num(a.atan());
     Ok(r)
@@ -367,6 +679,26 @@ fn len(_vm: &mut VM, a: Val) -> Result<Val, String> {
     Ok(Val::Num(len as f64))
 }
 
+fn encode(_vm: &mut VM, a: Val) -> Result<Val, String> {
+    let s = crate::serialize::encode(&a)?;
+    Ok(Val::Str(s))
+}
+
+fn decode(_vm: &mut VM, s: Val) -> Result<Val, String> {
+    let s = s.to_string();
+    crate::serialize::decode(&s)
+}
+
+fn to_json(_vm: &mut VM, a: Val) -> Result<Val, String> {
+    let s = crate::json::to_json(&a)?;
+    Ok(Val::Str(s))
+}
+
+fn from_json(_vm: &mut VM, s: Val) -> Result<Val, String> {
+    let s = s.to_string();
+    crate::json::from_json(&s)
+}
+
 fn ord(_vm: &mut VM, s: Val) -> Result<Val, String> {
     let s = s.to_string();
     if s.is_empty() {
@@ -398,6 +730,243 @@ fn lower(_vm: &mut VM, s: Val) -> Result<Val, String> {
     Ok(Val::Str(s.to_lowercase()))
 }
 
+// Accepts raw bytes (or a string, encoded as its UTF-8 bytes) instead of
+// forcing a round trip through a UTF-8 String. `url_safe` selects the
+// `-`/`_` alphabet (RFC 4648 section 5) in place of `+`/`/`, same
+// convention as numbase/strbase taking their radix as a second arg.
+fn base64_encode(_vm: &mut VM, a: Val, url_safe: Val) -> Result<Val, String> {
+    let bytes = match a {
+        Val::Bytes(a) => a.borrow().clone(),
+        a => a.to_string().into_bytes(),
+    };
+    let engine = if url_safe.truth() {
+        base64::engine::general_purpose::URL_SAFE
+    } else {
+        base64::engine::general_purpose::STANDARD
+    };
+    Ok(Val::Str(engine.encode(bytes)))
+}
+
+// Returns a Val::Bytes instead of requiring the decoded data to be valid
+// UTF-8. `url_safe` mirrors base64_encode's flag.
+fn base64_decode(_vm: &mut VM, s: Val, url_safe: Val) -> Result<Val, String> {
+    let s = s.to_string();
+    let engine = if url_safe.truth() {
+        base64::engine::general_purpose::URL_SAFE
+    } else {
+        base64::engine::general_purpose::STANDARD
+    };
+    match engine.decode(s) {
+        Ok(a) => Ok(Val::bytes(a)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+// Bytes-aware like base64_encode/base64_decode above, but hex's alphabet
+// (0-9a-f) has no character that needs a URL-safe substitute, so there's
+// no flag to mirror.
+fn hex_encode(_vm: &mut VM, a: Val) -> Result<Val, String> {
+    let bytes = match a {
+        Val::Bytes(a) => a.borrow().clone(),
+        a => a.to_string().into_bytes(),
+    };
+    Ok(Val::Str(hex::encode(bytes)))
+}
+
+fn hex_decode(_vm: &mut VM, s: Val) -> Result<Val, String> {
+    let s = s.to_string();
+    match hex::decode(s) {
+        Ok(a) => Ok(Val::bytes(a)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn bytes_to_str(_vm: &mut VM, a: Val) -> Result<Val, String> {
+    let bytes = match a {
+        Val::Bytes(a) => a.borrow().clone(),
+        _ => return Err("Not bytes".to_string()),
+    };
+    match String::from_utf8(bytes) {
+        Ok(s) => Ok(Val::Str(s)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn str_to_bytes(_vm: &mut VM, s: Val) -> Result<Val, String> {
+    let s = s.to_string();
+    Ok(Val::bytes(s.into_bytes()))
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// A from-scratch RFC 4648 implementation (as opposed to base64_encode
+// above, which delegates to the base64 crate), operating on a Str's UTF-8
+// bytes and returning a Str
+fn base64enc(_vm: &mut VM, s: Val) -> Result<Val, String> {
+    let bytes = s.to_string().into_bytes();
+    let mut r = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b0 = chunk[0];
+        let b1 = *chunk.get(1).unwrap_or(&0);
+        let b2 = *chunk.get(2).unwrap_or(&0);
+        r.push(BASE64_ALPHABET[(b0 >> 2) as usize] as char);
+        r.push(BASE64_ALPHABET[(((b0 & 0x03) << 4) | (b1 >> 4)) as usize] as char);
+        r.push(if chunk.len() > 1 {
+            BASE64_ALPHABET[(((b1 & 0x0f) << 2) | (b2 >> 6)) as usize] as char
+        } else {
+            '='
+        });
+        r.push(if chunk.len() > 2 {
+            BASE64_ALPHABET[(b2 & 0x3f) as usize] as char
+        } else {
+            '='
+        });
+    }
+    Ok(Val::Str(r))
+}
+
+fn base64_digit(c: u8) -> Result<u8, String> {
+    match c {
+        b'A'..=b'Z' => Ok(c - b'A'),
+        b'a'..=b'z' => Ok(c - b'a' + 26),
+        b'0'..=b'9' => Ok(c - b'0' + 52),
+        b'+' => Ok(62),
+        b'/' => Ok(63),
+        _ => Err(format!("Invalid base64 character: {}", c as char)),
+    }
+}
+
+fn base64dec(_vm: &mut VM, s: Val) -> Result<Val, String> {
+    let s = s.to_string();
+    let s = s.trim_end_matches('=');
+    let chars: Vec<u8> = s.bytes().collect();
+    let mut bytes = Vec::with_capacity(chars.len() * 3 / 4);
+    for group in chars.chunks(4) {
+        // A single leftover digit can't decode to anything: 6 bits isn't
+        // enough to reconstitute even one byte, so the input is malformed
+        // rather than just missing its '=' padding
+        if group.len() < 2 {
+            return Err("Invalid base64 length".to_string());
+        }
+        let digits: Vec<u8> = group
+            .iter()
+            .map(|c| base64_digit(*c))
+            .collect::<Result<_, _>>()?;
+        let d0 = digits[0];
+        let d1 = digits[1];
+        bytes.push((d0 << 2) | (d1 >> 4));
+        if let Some(d2) = digits.get(2) {
+            bytes.push((d1 << 4) | (d2 >> 2));
+            if let Some(d3) = digits.get(3) {
+                bytes.push((d2 << 6) | d3);
+            }
+        }
+    }
+    match String::from_utf8(bytes) {
+        Ok(s) => Ok(Val::Str(s)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn hex_digit(c: u8) -> Result<u8, String> {
+    match c {
+        b'0'..=b'9' => Ok(c - b'0'),
+        b'a'..=b'f' => Ok(c - b'a' + 10),
+        b'A'..=b'F' => Ok(c - b'A' + 10),
+        _ => Err(format!("Invalid hex digit: {}", c as char)),
+    }
+}
+
+// A from-scratch implementation (as opposed to hex_encode above, which
+// delegates to the hex crate), operating on a Str's UTF-8 bytes and
+// returning a Str
+fn hexenc(_vm: &mut VM, s: Val) -> Result<Val, String> {
+    let bytes = s.to_string().into_bytes();
+    let mut r = String::with_capacity(bytes.len() * 2);
+    for b in bytes {
+        r.push_str(&format!("{:02x}", b));
+    }
+    Ok(Val::Str(r))
+}
+
+fn hexdec(_vm: &mut VM, s: Val) -> Result<Val, String> {
+    let s = s.to_string();
+    let chars: Vec<u8> = s.bytes().collect();
+    if chars.len() % 2 != 0 {
+        return Err("Odd number of hex digits".to_string());
+    }
+    let mut bytes = Vec::with_capacity(chars.len() / 2);
+    for pair in chars.chunks(2) {
+        let hi = hex_digit(pair[0])?;
+        let lo = hex_digit(pair[1])?;
+        bytes.push((hi << 4) | lo);
+    }
+    match String::from_utf8(bytes) {
+        Ok(s) => Ok(Val::Str(s)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn sha256(_vm: &mut VM, s: Val) -> Result<Val, String> {
+    use sha2::{Digest, Sha256};
+    let mut hasher = Sha256::new();
+    hasher.update(s.to_string().as_bytes());
+    Ok(Val::Str(hex::encode(hasher.finalize())))
+}
+
+fn sha1(_vm: &mut VM, s: Val) -> Result<Val, String> {
+    use sha1::{Digest, Sha1};
+    let mut hasher = Sha1::new();
+    hasher.update(s.to_string().as_bytes());
+    Ok(Val::Str(hex::encode(hasher.finalize())))
+}
+
+fn md5(_vm: &mut VM, s: Val) -> Result<Val, String> {
+    use md5::{Digest, Md5};
+    let mut hasher = Md5::new();
+    hasher.update(s.to_string().as_bytes());
+    Ok(Val::Str(hex::encode(hasher.finalize())))
+}
+
+fn blake2b(_vm: &mut VM, s: Val) -> Result<Val, String> {
+    use blake2::{Blake2b512, Digest};
+    let mut hasher = Blake2b512::new();
+    hasher.update(s.to_string().as_bytes());
+    Ok(Val::Str(hex::encode(hasher.finalize())))
+}
+
+// Keyed HMAC, dispatching to the same algorithm family named by `algo`
+// ("sha256", "sha1", or "md5") as the corresponding hash builtin above
+fn hmac(_vm: &mut VM, key: Val, msg: Val, algo: Val) -> Result<Val, String> {
+    use hmac::{Hmac, Mac};
+    let key = key.to_string();
+    let msg = msg.to_string();
+    let algo = algo.to_string();
+    let r = match algo.as_str() {
+        "sha256" => {
+            let mut mac = Hmac::<sha2::Sha256>::new_from_slice(key.as_bytes())
+                .map_err(|e| e.to_string())?;
+            mac.update(msg.as_bytes());
+            hex::encode(mac.finalize().into_bytes())
+        }
+        "sha1" => {
+            let mut mac = Hmac::<sha1::Sha1>::new_from_slice(key.as_bytes())
+                .map_err(|e| e.to_string())?;
+            mac.update(msg.as_bytes());
+            hex::encode(mac.finalize().into_bytes())
+        }
+        "md5" => {
+            let mut mac =
+                Hmac::<md5::Md5>::new_from_slice(key.as_bytes()).map_err(|e| e.to_string())?;
+            mac.update(msg.as_bytes());
+            hex::encode(mac.finalize().into_bytes())
+        }
+        _ => return Err(format!("Unknown HMAC algorithm: {}", algo)),
+    };
+    Ok(Val::Str(r))
+}
+
 // Register all functions to the VM
 pub fn register_all(vm: &mut VM) {
     vm.registerv("_list", _list);
@@ -405,24 +974,46 @@ pub fn register_all(vm: &mut VM) {
     vm.register1("abs", abs);
     vm.register1("acos", acos);
     vm.register1("acosh", acosh);
+    vm.register1("arg", arg);
     vm.register1("asin", asin);
     vm.register1("asinh", asinh);
     vm.register1("atan", atan);
     vm.register2("atan2", atan2);
     vm.register1("atanh", atanh);
+    vm.register2("base64_decode", base64_decode);
+    vm.register2("base64_encode", base64_encode);
+    vm.register1("base64dec", base64dec);
+    vm.register1("base64enc", base64enc);
+    vm.register1("blake2b", blake2b);
+    vm.register1("bytes_to_str", bytes_to_str);
     vm.register1("cbrt", cbrt);
     vm.register1("ceil", ceil);
     vm.register1("chr", chr);
+    vm.register1("collect", collect);
+    vm.register2("complex", complex);
+    vm.register1("conj", conj);
     vm.register2("copysign", copysign);
     vm.register1("cos", cos);
     vm.register1("cosh", cosh);
+    vm.register1("decode", decode);
+    vm.register1("denom", denom);
+    vm.register1("encode", encode);
     vm.register1("exp", exp);
     vm.register1("exp2", exp2);
     vm.register1("expm1", expm1);
+    vm.register2("filter", filter);
     vm.register1("finite?", is_finite);
     vm.register1("floor", floor);
     vm.register3("fma", fma);
+    vm.register3("format_num", format_num);
+    vm.register1("from_json", from_json);
+    vm.register1("hex_decode", hex_decode);
+    vm.register1("hex_encode", hex_encode);
+    vm.register1("hexdec", hexdec);
+    vm.register1("hexenc", hexenc);
+    vm.register3("hmac", hmac);
     vm.register2("hypot", hypot);
+    vm.register1("im", im);
     vm.register1("inf?", is_inf);
     vm.register0("input", input);
     vm.register1("len", len);
@@ -431,25 +1022,45 @@ pub fn register_all(vm: &mut VM) {
     vm.register1("log1p", log1p);
     vm.register1("log2", log2);
     vm.register1("lower", lower);
+    vm.register2("map", map);
     vm.register2("max", max);
     vm.register2("min", min);
+    vm.register1("md5", md5);
+    vm.register1("modulus", modulus);
     vm.register1("nan?", is_nan);
     vm.register1("normal?", is_normal);
+    vm.register0("now", now);
     vm.register1("num", num);
     vm.register2("numbase", numbase);
+    vm.register1("number_type", number_type);
+    vm.register1("numer", numer);
     vm.register1("ord", ord);
+    vm.register2("parse_radix", parse_radix);
+    vm.register3("range", range);
+    vm.register2("rat", rat);
+    vm.register1("re", re);
+    vm.register3("reduce", reduce);
+    vm.register1("reseed", reseed);
     vm.register0("rnd", rnd);
+    vm.register1("rndbool", rndbool);
     vm.register1("round", round);
     vm.register1("roundeven", roundeven);
+    vm.register1("sha1", sha1);
+    vm.register1("sha256", sha256);
     vm.register1("sin", sin);
     vm.register1("sinh", sinh);
     vm.register1("sqrt", sqrt);
     vm.register1("str", str_);
+    vm.register1("str_to_bytes", str_to_bytes);
     vm.register2("strbase", strbase);
     vm.register1("subnormal?", is_subnormal);
+    vm.register2("take", take);
     vm.register1("tan", tan);
     vm.register1("tanh", tanh);
+    vm.register1("to_json", to_json);
+    vm.register2("to_radix", to_radix);
     vm.register1("trunc", trunc);
     vm.register1("typeof", typeof_);
     vm.register1("upper", upper);
+    vm.register2("zip", zip);
 }
@@ -1,4 +1,5 @@
 use std::rc::Rc;
+use unicode_segmentation::UnicodeSegmentation;
 
 #[derive(PartialEq)]
 pub struct Str32 {
@@ -67,27 +68,59 @@ impl Str32 {
     }
 
     // Returns a new Str32 with all uppercase characters
+    // Uses the full Unicode expansion (e.g. 'ß' -> "SS") rather than truncating to one char
     pub fn upper(&self) -> Self {
-        let r: Vec<char> = self
-            .v
-            .iter()
-            .map(|c| c.to_uppercase().next().unwrap_or(*c))
-            .collect();
+        let r: Vec<char> = self.v.iter().flat_map(|c| c.to_uppercase()).collect();
 
         Self { v: r.into() }
     }
 
     // Returns a new Str32 with all lowercase characters
+    // Uses the full Unicode expansion rather than truncating to one char
     pub fn lower(&self) -> Self {
-        let r: Vec<char> = self
-            .v
-            .iter()
-            .map(|c| c.to_lowercase().next().unwrap_or(*c))
-            .collect();
+        let r: Vec<char> = self.v.iter().flat_map(|c| c.to_lowercase()).collect();
 
         Self { v: r.into() }
     }
 
+    // Returns a Unicode case-folded form, approximated via full lowercase expansion
+    // Suitable for case-insensitive comparison, not for display
+    pub fn fold(&self) -> Self {
+        let r: Vec<char> = self.v.iter().flat_map(|c| c.to_lowercase()).collect();
+
+        Self { v: r.into() }
+    }
+
+    // Compares two strings for equality after case folding
+    pub fn eq_fold(&self, other: &Self) -> bool {
+        self.fold().v == other.fold().v
+    }
+
+    // Returns the number of extended grapheme clusters (user-perceived characters)
+    // This can be less than len() for strings containing combining marks or emoji
+    pub fn grapheme_len(&self) -> usize {
+        self.to_string().graphemes(true).count()
+    }
+
+    // Splits into its extended grapheme clusters, each as its own Str32
+    pub fn graphemes(&self) -> Vec<Self> {
+        self.to_string().graphemes(true).map(Self::new).collect()
+    }
+
+    // Accesses the grapheme cluster at a specific index, returning an error if out of bounds
+    pub fn grapheme_at(&self, i: usize) -> Result<Self, String> {
+        match self.to_string().graphemes(true).nth(i) {
+            Some(g) => Ok(Self::new(g)),
+            None => Err("Index out of range".to_string()),
+        }
+    }
+
+    // Creates a substring from a range of grapheme cluster indices, rather than char indices
+    pub fn grapheme_substr(&self, i: usize, j: usize) -> Self {
+        let graphemes: Vec<&str> = self.to_string().graphemes(true).collect();
+        Self::new(&graphemes[i..j].concat())
+    }
+
     // Creates a new Str32 that repeats the current content n times
     pub fn repeat(&self, n: usize) -> Self {
         let mut r: Vec<char> = Vec::with_capacity(self.len() * n);
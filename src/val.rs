@@ -1,27 +1,86 @@
 use crate::VM;
 use crate::list::*;
+use crate::object::Object;
+use num_bigint::BigInt;
+use num_complex::Complex64;
+use num_rational::BigRational;
+use num_traits::{One, ToPrimitive, Zero};
 use std::cell::RefCell;
 use std::fmt;
 use std::rc::Rc;
 
+// A user-defined function value: the VM jumps to `pc` on call, binding
+// `params` to the popped arguments and seeding the local scope with
+// `captured`, a snapshot of the free variables (named by `free`, same
+// order) taken when the closure was created, so it keeps working after
+// its defining scope is gone.
+//
+// `slots` is the frame size a compile-time resolution pass assigned this
+// closure's body: params occupy slots `0..params.len()`, `captured`
+// occupies the next `free.len()` slots, and any remaining slots are the
+// function's own locals. The VM's call frame is then a plain `Vec<Val>`
+// indexed by slot instead of a `HashMap` keyed by name.
+pub struct ClosureVal {
+    pub params: Vec<String>,
+    pub free: Vec<String>,
+    pub captured: Vec<Val>,
+    pub pc: usize,
+    pub slots: usize,
+}
+
 #[derive(Clone)]
 pub enum Val {
     // Value semantics
     True,
     False,
     Null,
+    // Arbitrary-precision integer, kept distinct from Num so exact integer
+    // arithmetic (factorials, bit masks, ...) doesn't degrade to f64 precision
+    Int(BigInt),
+    // Exact fraction, always stored reduced (num-rational does this via gcd).
+    // Sits between Int and Num in the numeric tower: dividing two Ints stays
+    // exact instead of dropping to float, but a Ratio collapses to Int the
+    // moment its denominator reduces to 1 (see `Val::ratio`)
+    Ratio(BigRational),
     Num(f64),
+    // A complex number, value semantics like Num since num-complex's Complex
+    // is Copy-able. Not part of the Int/Ratio/Num promotion tower: reals
+    // only become Complex by an explicit `complex(...)` call or when a
+    // transcendental function (sqrt, ...) needs to return one
+    Complex(Complex64),
     Str(String),
 
     // Reference semantics
     List(Rc<RefCell<List>>),
 
+    // A map/dictionary, shared like List via Rc<RefCell<_>> so mutation is
+    // visible through every handle
+    Object(Rc<RefCell<Object>>),
+
+    // A byte string: shares List's Rc<RefCell<_>> handle semantics (cloning
+    // hands out another reference to the same buffer), but unlike List it
+    // compares by contents rather than identity, since bytes are meant to be
+    // used as opaque encoded data (base64, hex, ...) rather than as a mutable
+    // shared structure.
+    Bytes(Rc<RefCell<Vec<u8>>>),
+
+    // A lazy, pull-based sequence: calling the wrapped closure yields the
+    // next value or None once exhausted. Shared via Rc<RefCell<_>> like
+    // List, so cloning an iterator hands out another handle onto the same
+    // cursor rather than restarting it. Lets pipelines (range/map/filter/...)
+    // compose without materializing intermediate lists.
+    Iter(Rc<RefCell<dyn FnMut(&mut VM) -> Result<Option<Val>, String>>>),
+
     // Functions of various arities
     Func0(Rc<dyn Fn(&mut VM) -> Result<Val, String>>),
     Func1(Rc<dyn Fn(&mut VM, Val) -> Result<Val, String>>),
     Func2(Rc<dyn Fn(&mut VM, Val, Val) -> Result<Val, String>>),
     Func3(Rc<dyn Fn(&mut VM, Val, Val, Val) -> Result<Val, String>>),
     FuncV(Rc<dyn Fn(&mut VM, Vec<Val>) -> Result<Val, String>>),
+
+    // A user-defined (Verbena-level) function, as opposed to the native
+    // Func0..FuncV wrappers above
+    Closure(Rc<ClosureVal>),
 }
 
 impl Val {
@@ -64,6 +123,28 @@ impl Val {
         Val::FuncV(Rc::new(f))
     }
 
+    pub fn closure(params: Vec<String>, free: Vec<String>, captured: Vec<Val>, pc: usize, slots: usize) -> Self {
+        Val::Closure(Rc::new(ClosureVal {
+            params,
+            free,
+            captured,
+            pc,
+            slots,
+        }))
+    }
+
+    pub fn bytes(v: Vec<u8>) -> Self {
+        Val::Bytes(Rc::new(RefCell::new(v)))
+    }
+
+    pub fn object(o: Object) -> Self {
+        Val::Object(Rc::new(RefCell::new(o)))
+    }
+
+    pub fn complex(re: f64, im: f64) -> Self {
+        Val::Complex(Complex64::new(re, im))
+    }
+
     pub fn as_string(&self) -> Result<String, String> {
         let r = match self {
             Val::Str(s) => s.to_string(),
@@ -74,8 +155,10 @@ impl Val {
 
     pub fn num(&self) -> Result<Val, String> {
         let r = match self {
-            Val::True => Val::Num(1.0),
-            Val::False => Val::Num(0.0),
+            Val::True => Val::Int(BigInt::one()),
+            Val::False => Val::Int(BigInt::zero()),
+            Val::Int(_) => self.clone(),
+            Val::Ratio(_) => self.clone(),
             Val::Num(_) => self.clone(),
             _ => return Err("Not a number".to_string()),
         };
@@ -84,113 +167,267 @@ impl Val {
 
     pub fn num_loose(&self) -> Val {
         match self {
-            Val::True => Val::Num(1.0),
-            Val::False => Val::Num(0.0),
+            Val::True => Val::Int(BigInt::one()),
+            Val::False => Val::Int(BigInt::zero()),
             _ => self.clone(),
         }
     }
 
+    // Normalizes a BigRational into a Val, collapsing to Int the moment it's
+    // exactly whole (e.g. 6/3) instead of carrying a denominator of 1 around
+    pub fn ratio(r: BigRational) -> Val {
+        if r.is_integer() { Val::Int(r.to_integer()) } else { Val::Ratio(r) }
+    }
+
+    // Exact conversion, as opposed to `to_f64`'s lossy one: errors on
+    // non-finite floats instead of silently truncating them
+    pub fn to_bigint(&self) -> Result<BigInt, String> {
+        match self {
+            Val::True => Ok(BigInt::one()),
+            Val::False => Ok(BigInt::zero()),
+            Val::Int(a) => Ok(a.clone()),
+            Val::Ratio(a) => {
+                if !a.is_integer() {
+                    return Err("Not an integer".to_string());
+                }
+                Ok(a.to_integer())
+            }
+            Val::Num(a) => {
+                if !a.is_finite() {
+                    return Err("Not a finite number".to_string());
+                }
+                Ok(BigInt::from(*a as i64))
+            }
+            _ => Err("Not a number".to_string()),
+        }
+    }
+
+    // Runtime counterpart to the lexer's 0x/0b/0o literals: formats an
+    // integer in any base 2..=36, sign preserved, via BigInt::to_str_radix
+    pub fn to_radix(&self, base: u32) -> Result<String, String> {
+        if !(2..=36).contains(&base) {
+            return Err("radix out of range".to_string());
+        }
+        let a = self.to_bigint()?;
+        Ok(a.to_str_radix(base))
+    }
+
+    // Runtime counterpart to the lexer's 0x/0b/0o literals: parses an
+    // integer in any base 2..=36, tolerating the same whitespace/sign/
+    // underscore conventions as numeric literals instead of panicking on
+    // malformed input
+    pub fn parse_radix(s: &str, base: u32) -> Result<Val, String> {
+        if !(2..=36).contains(&base) {
+            return Err("radix out of range".to_string());
+        }
+        let s: String = s.trim().chars().filter(|c| *c != '_').collect();
+        if s.is_empty() {
+            return Err("Empty input".to_string());
+        }
+        match BigInt::from_str_radix(&s, base) {
+            Ok(a) => Ok(Val::Int(a)),
+            Err(_) => Err("Invalid digit".to_string()),
+        }
+    }
+
     pub fn to_u32(&self) -> Result<u32, String> {
-        let r = match self.num()? {
+        match self.num()? {
+            Val::Int(a) => a.to_u32().ok_or_else(|| "Integer out of range".to_string()),
             Val::Num(a) => {
                 if !a.is_finite() {
                     return Err("Not a finite number".to_string());
                 }
-                a as u32
+                Ok(a as u32)
             }
             _ => panic!(),
-        };
-        Ok(r)
+        }
     }
 
     pub fn to_i32(&self) -> Result<i32, String> {
-        let r = match self.num()? {
+        match self.num()? {
+            Val::Int(a) => a.to_i32().ok_or_else(|| "Integer out of range".to_string()),
             Val::Num(a) => {
                 if !a.is_finite() {
                     return Err("Not a finite number".to_string());
                 }
-                a as i32
+                Ok(a as i32)
             }
             _ => panic!(),
-        };
-        Ok(r)
+        }
     }
 
     pub fn to_i64(&self) -> Result<i64, String> {
-        let r = match self.num()? {
+        match self.num()? {
+            Val::Int(a) => a.to_i64().ok_or_else(|| "Integer out of range".to_string()),
             Val::Num(a) => {
                 if !a.is_finite() {
                     return Err("Not a finite number".to_string());
                 }
-                a as i64
+                Ok(a as i64)
             }
             _ => panic!(),
-        };
-        Ok(r)
+        }
     }
 
     pub fn to_u64(&self) -> Result<u64, String> {
-        let r = match self.num()? {
+        match self.num()? {
+            Val::Int(a) => a.to_u64().ok_or_else(|| "Integer out of range".to_string()),
             Val::Num(a) => {
                 if !a.is_finite() {
                     return Err("Not a finite number".to_string());
                 }
-                a as u64
+                Ok(a as u64)
             }
             _ => panic!(),
-        };
-        Ok(r)
+        }
     }
 
     pub fn to_usize(&self) -> Result<usize, String> {
-        let r = match self.num()? {
+        match self.num()? {
+            Val::Int(a) => a.to_usize().ok_or_else(|| "Integer out of range".to_string()),
             Val::Num(a) => {
                 if !a.is_finite() {
                     return Err("Not a finite number".to_string());
                 }
-                a as usize
+                Ok(a as usize)
             }
             _ => panic!(),
-        };
-        Ok(r)
+        }
     }
 
     pub fn to_isize(&self) -> Result<isize, String> {
-        let r = match self.num()? {
+        match self.num()? {
+            Val::Int(a) => a.to_isize().ok_or_else(|| "Integer out of range".to_string()),
             Val::Num(a) => {
                 if !a.is_finite() {
                     return Err("Not a finite number".to_string());
                 }
-                a as isize
+                Ok(a as isize)
             }
             _ => panic!(),
-        };
-        Ok(r)
+        }
     }
 
+    // Lossy conversion: unlike `to_bigint`, never fails, saturating
+    // infinite/out-of-range integers to +/-inf instead
     pub fn to_f64(&self) -> Result<f64, String> {
         let r = match self {
             Val::True => 1.0,
             Val::False => 0.0,
+            Val::Int(a) => a.to_f64().unwrap_or(if *a < BigInt::zero() { f64::NEG_INFINITY } else { f64::INFINITY }),
+            // Evaluated as numer/denom rather than via num-rational's own
+            // ToPrimitive impl, which can overflow well before the true
+            // quotient would
+            Val::Ratio(a) => {
+                let numer = a.numer().to_f64().unwrap_or(if *a.numer() < BigInt::zero() { f64::NEG_INFINITY } else { f64::INFINITY });
+                let denom = a.denom().to_f64().unwrap_or(f64::INFINITY);
+                numer / denom
+            }
             Val::Num(a) => *a,
             _ => return Err("Not a number".to_string()),
         };
         Ok(r)
     }
 
+    // A structured, type-revealing representation for debugging/REPL use,
+    // as opposed to Display which renders the value as a script would see it
+    // (e.g. strings are quoted here, but bare when printed by the script)
+    pub fn inspect(&self) -> String {
+        match self {
+            Val::True => "true".to_string(),
+            Val::False => "false".to_string(),
+            Val::Null => "null".to_string(),
+            Val::Int(a) => a.to_string(),
+            Val::Ratio(a) => format!("{}/{}", a.numer(), a.denom()),
+            Val::Num(a) => a.to_string(),
+            Val::Str(s) => format!("{:?}", s),
+            Val::List(a) => {
+                let a = a.borrow();
+                let items: Vec<String> = a.v.iter().map(Val::inspect).collect();
+                format!("[{}]", items.join(", "))
+            }
+            Val::Bytes(a) => {
+                let a = a.borrow();
+                let hex: String = a.iter().map(|b| format!("{:02x}", b)).collect();
+                format!("b\"{}\"", hex)
+            }
+            Val::Object(a) => {
+                let a = a.borrow();
+                let items: Vec<String> = a
+                    .iter()
+                    .map(|(k, v)| format!("{:?}: {}", k, v.inspect()))
+                    .collect();
+                format!("{{{}}}", items.join(", "))
+            }
+            Val::Complex(a) => format_complex(*a),
+            Val::Func0(_) => "<fn/0>".to_string(),
+            Val::Func1(_) => "<fn/1>".to_string(),
+            Val::Func2(_) => "<fn/2>".to_string(),
+            Val::Func3(_) => "<fn/3>".to_string(),
+            Val::FuncV(_) => "<fn/*>".to_string(),
+            Val::Closure(c) => format!("<fn/{}>", c.params.len()),
+            Val::Iter(_) => "<iter>".to_string(),
+        }
+    }
+
     pub fn truth(&self) -> bool {
         match self {
             Val::False | Val::Null => false,
+            Val::Int(a) => !a.is_zero(),
+            Val::Ratio(a) => !a.numer().is_zero(),
             Val::Num(a) => *a != 0.0,
+            Val::Complex(a) => a.re != 0.0 || a.im != 0.0,
             Val::Str(s) => !s.is_empty(),
+            Val::Bytes(a) => !a.borrow().is_empty(),
             _ => true,
         }
     }
 }
 
-pub fn eq_loose(a: &Val, b: &Val) -> bool {
+// Renders a complex number as "a+bi" ("a-bi" when the imaginary part is
+// negative), the conventional engineering notation
+fn format_complex(c: Complex64) -> String {
+    if c.im < 0.0 {
+        format!("{}-{}i", c.re, -c.im)
+    } else {
+        format!("{}+{}i", c.re, c.im)
+    }
+}
+
+// Widens whichever side is "lower" on the numeric tower Int < Ratio < Num so
+// arithmetic can match on a uniform (Val, Val) shape: pairing with a Num
+// collapses both sides to f64, pairing an Int with a Ratio lifts the Int to
+// a Ratio with denominator 1. Two Ints (or two Ratios, or two Nums) pass
+// through unchanged.
+fn promote_pair(a: Val, b: Val) -> (Val, Val) {
+    match (&a, &b) {
+        (Val::Num(_), Val::Num(_)) => (a, b),
+        (Val::Num(_), _) => (a, Val::Num(b.to_f64().unwrap_or(f64::INFINITY))),
+        (_, Val::Num(_)) => (Val::Num(a.to_f64().unwrap_or(f64::INFINITY)), b),
+        (Val::Ratio(_), Val::Int(y)) => (a, Val::Ratio(BigRational::from_integer(y.clone()))),
+        (Val::Int(x), Val::Ratio(_)) => (Val::Ratio(BigRational::from_integer(x.clone())), b),
+        _ => (a, b),
+    }
+}
+
+// The pairwise, strict counterpart to `num`: errors if either side isn't numeric.
+pub fn num2(a: &Val, b: &Val) -> Result<(Val, Val), String> {
+    let a = a.num()?;
+    let b = b.num()?;
+    Ok(promote_pair(a, b))
+}
+
+// The pairwise counterpart to `num_loose`, so binary ops can match on a
+// uniform (Val, Val) shape without losing the exactness of a lone Val::Int
+// operand (it's only widened to Num when paired with one).
+pub fn num2_loose(a: &Val, b: &Val) -> (Val, Val) {
     let a = a.num_loose();
     let b = b.num_loose();
+    promote_pair(a, b)
+}
+
+pub fn eq_loose(a: &Val, b: &Val) -> bool {
+    let (a, b) = num2_loose(a, b);
     match (&a, &b) {
         // TODO: is this needed?
         (Val::Func0(a), Val::Func0(b)) => Rc::ptr_eq(a, b),
@@ -199,10 +436,11 @@ pub fn eq_loose(a: &Val, b: &Val) -> bool {
 }
 
 pub fn lt_loose(a: &Val, b: &Val) -> bool {
-    let a = a.num_loose();
-    let b = b.num_loose();
+    let (a, b) = num2_loose(a, b);
     match (&a, &b) {
         (Val::Num(a), Val::Num(b)) => a < b,
+        (Val::Int(a), Val::Int(b)) => a < b,
+        (Val::Ratio(a), Val::Ratio(b)) => a < b,
         _ => {
             let a = a.to_string();
             let b = b.to_string();
@@ -212,10 +450,11 @@ pub fn lt_loose(a: &Val, b: &Val) -> bool {
 }
 
 pub fn le_loose(a: &Val, b: &Val) -> bool {
-    let a = a.num_loose();
-    let b = b.num_loose();
+    let (a, b) = num2_loose(a, b);
     match (&a, &b) {
         (Val::Num(a), Val::Num(b)) => a <= b,
+        (Val::Int(a), Val::Int(b)) => a <= b,
+        (Val::Ratio(a), Val::Ratio(b)) => a <= b,
         _ => {
             let a = a.to_string();
             let b = b.to_string();
@@ -230,9 +469,18 @@ impl fmt::Display for Val {
             Val::True => write!(f, "true"),
             Val::False => write!(f, "false"),
             Val::Null => write!(f, "null"),
+            Val::Int(a) => write!(f, "{}", a),
+            Val::Ratio(a) => write!(f, "{}/{}", a.numer(), a.denom()),
             Val::Num(a) => write!(f, "{}", a),
+            Val::Complex(a) => write!(f, "{}", format_complex(*a)),
             Val::Str(s) => write!(f, "{}", s),
             Val::List(a) => write!(f, "{}", a.borrow()),
+            Val::Bytes(a) => {
+                let hex: String = a.borrow().iter().map(|b| format!("{:02x}", b)).collect();
+                write!(f, "b\"{}\"", hex)
+            }
+            Val::Object(a) => write!(f, "{}", a.borrow()),
+            Val::Iter(_) => write!(f, "<iter>"),
             // TODO
             _ => write!(f, "<fn>"),
         }
@@ -245,14 +493,21 @@ impl std::fmt::Debug for Val {
             Val::True => f.debug_tuple("True").finish(),
             Val::False => f.debug_tuple("False").finish(),
             Val::Null => f.debug_tuple("Null").finish(),
+            Val::Int(a) => f.debug_tuple("Int").field(a).finish(),
+            Val::Ratio(a) => f.debug_tuple("Ratio").field(a).finish(),
             Val::Num(a) => f.debug_tuple("Num").field(a).finish(),
+            Val::Complex(a) => f.debug_tuple("Complex").field(a).finish(),
             Val::Str(s) => f.debug_tuple("Str").field(s).finish(),
             Val::List(a) => f.debug_tuple("List").field(&a.borrow()).finish(),
+            Val::Bytes(a) => f.debug_tuple("Bytes").field(&a.borrow()).finish(),
+            Val::Object(a) => f.debug_tuple("Object").field(&a.borrow()).finish(),
             Val::Func0(_) => f.debug_tuple("Func0").field(&"...").finish(),
             Val::Func1(_) => f.debug_tuple("Func1").field(&"...").finish(),
             Val::Func2(_) => f.debug_tuple("Func2").field(&"...").finish(),
             Val::Func3(_) => f.debug_tuple("Func3").field(&"...").finish(),
             Val::FuncV(_) => f.debug_tuple("FuncV").field(&"...").finish(),
+            Val::Closure(c) => f.debug_tuple("Closure").field(&c.params).finish(),
+            Val::Iter(_) => f.debug_tuple("Iter").field(&"...").finish(),
         }
     }
 }
@@ -261,15 +516,23 @@ impl PartialEq for Val {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (Val::True, Val::True) | (Val::False, Val::False) | (Val::Null, Val::Null) => true,
+            (Val::Int(a), Val::Int(b)) => a == b,
+            (Val::Ratio(a), Val::Ratio(b)) => a == b,
             (Val::Num(a), Val::Num(b)) => a == b,
+            (Val::Complex(a), Val::Complex(b)) => a == b,
             (Val::Str(a), Val::Str(b)) => a == b,
             (Val::List(a), Val::List(b)) => a == b,
+            (Val::Object(a), Val::Object(b)) => a == b,
+            // Unlike List, Bytes compares by contents rather than identity
+            (Val::Bytes(a), Val::Bytes(b)) => *a.borrow() == *b.borrow(),
             // Functions are compared by reference equality
             (Val::Func0(a), Val::Func0(b)) => Rc::ptr_eq(a, b),
             (Val::Func1(a), Val::Func1(b)) => Rc::ptr_eq(a, b),
             (Val::Func2(a), Val::Func2(b)) => Rc::ptr_eq(a, b),
             (Val::Func3(a), Val::Func3(b)) => Rc::ptr_eq(a, b),
             (Val::FuncV(a), Val::FuncV(b)) => Rc::ptr_eq(a, b),
+            (Val::Closure(a), Val::Closure(b)) => Rc::ptr_eq(a, b),
+            (Val::Iter(a), Val::Iter(b)) => Rc::ptr_eq(a, b),
             // Different variant types are not equal
             _ => false,
         }
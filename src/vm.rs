@@ -1,18 +1,78 @@
 use crate::error_context::*;
+use crate::object::Object;
 use crate::program::*;
 use crate::stdlib::*;
 use crate::val::*;
 use num_bigint::BigInt;
-use num_traits::Zero;
+use num_rational::BigRational;
+use num_traits::{ToPrimitive, Zero};
 use rand::SeedableRng;
 use rand_chacha::ChaCha20Rng;
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::rc::Rc;
+use unicode_segmentation::UnicodeSegmentation;
+
+// Lets the VM's notion of "now" be swapped out, so scripts that read the clock
+// can still be tested deterministically instead of depending on wall-clock time
+pub trait Clock {
+    fn now(&self) -> f64;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> f64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64()
+    }
+}
+
+// A clock that only advances when told to, for deterministic tests
+pub struct VirtualClock {
+    pub t: RefCell<f64>,
+}
+
+impl VirtualClock {
+    pub fn new(t: f64) -> Self {
+        VirtualClock { t: RefCell::new(t) }
+    }
+
+    pub fn advance(&self, dt: f64) {
+        *self.t.borrow_mut() += dt;
+    }
+}
+
+impl Clock for VirtualClock {
+    fn now(&self) -> f64 {
+        *self.t.borrow()
+    }
+}
+
+// A single user-function call: where to resume the caller (`return_pc`),
+// the callee's own local scope (seeded from the closure's captured
+// variables and its arguments, slot for slot — see ClosureVal::slots),
+// and the operand stack depth to restore to on return so the callee's
+// working values don't leak to the caller.
+struct Frame {
+    return_pc: usize,
+    locals: Vec<Val>,
+    stack_base: usize,
+}
 
 pub struct VM {
     pub rng: ChaCha20Rng,
+    pub clock: Box<dyn Clock>,
     pub vars: HashMap<String, Val>,
+    frames: Vec<Frame>,
+    // (handler_pc, stack_len, catch_var) for each enclosing `try`, innermost last
+    handlers: Vec<(usize, usize, String)>,
+    // The program `run()` is currently executing, kept around so native
+    // builtins (map/filter/reduce/...) can call back into Verbena closures
+    // via `call_value` without needing their own copy of the bytecode
+    program: Option<Rc<Program>>,
 }
 
 fn slice_index(n: usize, i: isize) -> usize {
@@ -32,114 +92,288 @@ fn slice_indexes(n: usize, i: Val, j: Val) -> Result<(usize, usize), String> {
     Ok((slice_index(n, i), slice_index(n, j)))
 }
 
+// Wraps any iterable value as a `Val::Iter`, so List, Str, and Iter can all
+// be pulled from through the same "next" protocol instead of each caller
+// (for-loops, collect, reduce, ...) special-casing how they're walked.
+// Iter values pass through unchanged.
+pub fn to_iter(v: Val) -> Result<Val, String> {
+    match v {
+        Val::Iter(_) => Ok(v),
+        Val::List(list) => {
+            let mut i = 0usize;
+            Ok(Val::Iter(Rc::new(RefCell::new(move |_vm: &mut VM| {
+                let a = list.borrow();
+                if i >= a.v.len() {
+                    return Ok(None);
+                }
+                let r = a.v[i].clone();
+                i += 1;
+                Ok(Some(r))
+            }))))
+        }
+        Val::Str(s) => {
+            // Walk by grapheme cluster, consistent with how the VM indexes
+            // and slices strings elsewhere
+            let gs: Vec<String> = s.graphemes(true).map(|g| g.to_string()).collect();
+            let mut i = 0usize;
+            Ok(Val::Iter(Rc::new(RefCell::new(move |_vm: &mut VM| {
+                if i >= gs.len() {
+                    return Ok(None);
+                }
+                let r = Val::Str(gs[i].clone());
+                i += 1;
+                Ok(Some(r))
+            }))))
+        }
+        _ => Err("Not iterable".to_string()),
+    }
+}
+
+// Pulls the next value from an iterator previously produced by `to_iter`
+pub fn next(vm: &mut VM, it: &Val) -> Result<Option<Val>, String> {
+    match it {
+        Val::Iter(f) => (f.borrow_mut())(vm),
+        _ => Err("Not an iterator".to_string()),
+    }
+}
+
 fn error<S: AsRef<str>>(ec: &ErrorContext, msg: S) -> String {
     format!("{}: {}", ec, msg.as_ref())
 }
 
-fn sub(stack: &mut Vec<Val>) -> Result<(), String> {
-    let b = stack.pop().unwrap().to_f64()?;
-    let a = stack.pop().unwrap().to_f64()?;
-    let r = Val::Num(a - b);
+pub(crate) fn add(stack: &mut Vec<Val>) -> Result<(), String> {
+    let b = stack.pop().unwrap();
+    let a = stack.pop().unwrap();
+    let (a, b) = num2_loose(&a, &b);
+    let r = match (&a, &b) {
+        (Val::Int(a), Val::Int(b)) => Val::Int(a + b),
+        (Val::Num(a), Val::Num(b)) => Val::Num(a + b),
+        _ => {
+            let a = a.to_string();
+            let b = b.to_string();
+            let mut r = String::with_capacity(a.len() + b.len());
+            r.push_str(&a);
+            r.push_str(&b);
+            Val::Str(r)
+        }
+    };
     stack.push(r);
     Ok(())
 }
 
-fn neg(stack: &mut Vec<Val>) -> Result<(), String> {
-    let a = stack.pop().unwrap().to_f64()?;
-    let r = Val::Num(-a);
+pub(crate) fn sub(stack: &mut Vec<Val>) -> Result<(), String> {
+    let b = stack.pop().unwrap();
+    let a = stack.pop().unwrap();
+    let (a, b) = num2_loose(&a, &b);
+    let r = match (&a, &b) {
+        (Val::Int(a), Val::Int(b)) => Val::Int(a - b),
+        (Val::Num(a), Val::Num(b)) => Val::Num(a - b),
+        _ => return Err("Not numbers".to_string()),
+    };
     stack.push(r);
     Ok(())
 }
 
-// TODO: rename
-fn fdiv(stack: &mut Vec<Val>) -> Result<(), String> {
-    let b = stack.pop().unwrap().to_f64()?;
-    let a = stack.pop().unwrap().to_f64()?;
-    let r = Val::Num(a / b);
+pub(crate) fn neg(stack: &mut Vec<Val>) -> Result<(), String> {
+    let a = stack.pop().unwrap().num_loose();
+    let r = match a {
+        Val::Int(a) => Val::Int(-a),
+        Val::Num(a) => Val::Num(-a),
+        _ => return Err("Not a number".to_string()),
+    };
     stack.push(r);
     Ok(())
 }
 
-fn pow(stack: &mut Vec<Val>) -> Result<(), String> {
-    let b = stack.pop().unwrap().to_f64()?;
-    let a = stack.pop().unwrap().to_f64()?;
-    let r = Val::Num(a.powf(b));
+// True division, as opposed to IDiv's truncating integer division: Int/Int
+// (and Ratio/Ratio) stay exact, collapsing back to Int when the result is
+// whole (6/3 is Int(2), 1/3 is Ratio(1/3)); anything touching a Num
+// collapses to float.
+pub(crate) fn fdiv(stack: &mut Vec<Val>) -> Result<(), String> {
+    let b = stack.pop().unwrap();
+    let a = stack.pop().unwrap();
+    let (a, b) = num2_loose(&a, &b);
+    let r = match (&a, &b) {
+        (Val::Int(a), Val::Int(b)) => {
+            if b.is_zero() {
+                return Err("Division by zero".to_string());
+            }
+            Val::ratio(BigRational::new(a.clone(), b.clone()))
+        }
+        (Val::Ratio(a), Val::Ratio(b)) => {
+            if b.is_zero() {
+                return Err("Division by zero".to_string());
+            }
+            Val::ratio(a / b)
+        }
+        (Val::Num(a), Val::Num(b)) => Val::Num(a / b),
+        _ => return Err("Not numbers".to_string()),
+    };
     stack.push(r);
     Ok(())
 }
 
-fn bit_and(stack: &mut Vec<Val>) -> Result<(), String> {
-    let b = stack.pop().unwrap().to_f64()? as i64;
-    let a = stack.pop().unwrap().to_f64()? as i64;
-    let r = a & b;
-    let r = Val::Num(r as f64);
+// Large enough for any realistic exact computation but small enough to
+// reject the pathological allocation a naive `2 ** 10000000` would trigger
+const MAX_POW_BITS: u64 = 1_000_000;
+
+pub(crate) fn pow(stack: &mut Vec<Val>) -> Result<(), String> {
+    let b = stack.pop().unwrap();
+    let a = stack.pop().unwrap();
+    let (a, b) = num2_loose(&a, &b);
+    let r = match (&a, &b) {
+        (Val::Int(a), Val::Int(b)) => match b.to_u32() {
+            // Estimating the result's bit length as bits(a) * e is cheap and
+            // only ever over-estimates, so it can't let a real blowup through
+            Some(e) if a.bits().saturating_mul(e as u64) <= MAX_POW_BITS => Val::Int(a.pow(e)),
+            _ => Val::Num(a.to_f64().unwrap_or(f64::INFINITY).powf(b.to_f64().unwrap_or(f64::INFINITY))),
+        },
+        (Val::Num(a), Val::Num(b)) => Val::Num(a.powf(*b)),
+        _ => return Err("Not numbers".to_string()),
+    };
     stack.push(r);
     Ok(())
 }
 
-fn bit_or(stack: &mut Vec<Val>) -> Result<(), String> {
-    let b = stack.pop().unwrap().to_f64()? as i64;
-    let a = stack.pop().unwrap().to_f64()? as i64;
-    let r = a | b;
-    let r = Val::Num(r as f64);
+pub(crate) fn bit_and(stack: &mut Vec<Val>) -> Result<(), String> {
+    let b = stack.pop().unwrap();
+    let a = stack.pop().unwrap();
+    let (a, b) = num2_loose(&a, &b);
+    let r = match (&a, &b) {
+        (Val::Int(a), Val::Int(b)) => Val::Int(a & b),
+        _ => {
+            let b = b.to_f64()? as i64;
+            let a = a.to_f64()? as i64;
+            Val::Num((a & b) as f64)
+        }
+    };
     stack.push(r);
     Ok(())
 }
 
-fn bit_xor(stack: &mut Vec<Val>) -> Result<(), String> {
-    let b = stack.pop().unwrap().to_f64()? as i64;
-    let a = stack.pop().unwrap().to_f64()? as i64;
-    let r = a ^ b;
-    let r = Val::Num(r as f64);
+pub(crate) fn bit_or(stack: &mut Vec<Val>) -> Result<(), String> {
+    let b = stack.pop().unwrap();
+    let a = stack.pop().unwrap();
+    let (a, b) = num2_loose(&a, &b);
+    let r = match (&a, &b) {
+        (Val::Int(a), Val::Int(b)) => Val::Int(a | b),
+        _ => {
+            let b = b.to_f64()? as i64;
+            let a = a.to_f64()? as i64;
+            Val::Num((a | b) as f64)
+        }
+    };
     stack.push(r);
     Ok(())
 }
 
-fn shl(stack: &mut Vec<Val>) -> Result<(), String> {
-    let b = stack.pop().unwrap().to_f64()? as i64;
-    let a = stack.pop().unwrap().to_f64()? as i64;
-    let r = a << b;
-    let r = Val::Num(r as f64);
+pub(crate) fn bit_xor(stack: &mut Vec<Val>) -> Result<(), String> {
+    let b = stack.pop().unwrap();
+    let a = stack.pop().unwrap();
+    let (a, b) = num2_loose(&a, &b);
+    let r = match (&a, &b) {
+        (Val::Int(a), Val::Int(b)) => Val::Int(a ^ b),
+        _ => {
+            let b = b.to_f64()? as i64;
+            let a = a.to_f64()? as i64;
+            Val::Num((a ^ b) as f64)
+        }
+    };
+    stack.push(r);
+    Ok(())
+}
+
+pub(crate) fn shl(stack: &mut Vec<Val>) -> Result<(), String> {
+    let b = stack.pop().unwrap();
+    let a = stack.pop().unwrap();
+    let (a, b) = num2_loose(&a, &b);
+    let r = match (&a, &b) {
+        (Val::Int(a), Val::Int(b)) => {
+            let n = b.to_usize().ok_or_else(|| "Shift amount out of range".to_string())?;
+            Val::Int(a << n)
+        }
+        _ => {
+            let b = b.to_f64()? as i64;
+            let a = a.to_f64()? as i64;
+            Val::Num((a << b) as f64)
+        }
+    };
     stack.push(r);
     Ok(())
 }
 
 // TODO: >>>
-fn shr(stack: &mut Vec<Val>) -> Result<(), String> {
-    let b = stack.pop().unwrap().to_f64()? as i64;
-    let a = stack.pop().unwrap().to_f64()? as i64;
-    let r = a >> b;
-    let r = Val::Num(r as f64);
+pub(crate) fn shr(stack: &mut Vec<Val>) -> Result<(), String> {
+    let b = stack.pop().unwrap();
+    let a = stack.pop().unwrap();
+    let (a, b) = num2_loose(&a, &b);
+    let r = match (&a, &b) {
+        (Val::Int(a), Val::Int(b)) => {
+            let n = b.to_usize().ok_or_else(|| "Shift amount out of range".to_string())?;
+            Val::Int(a >> n)
+        }
+        _ => {
+            let b = b.to_f64()? as i64;
+            let a = a.to_f64()? as i64;
+            Val::Num((a >> b) as f64)
+        }
+    };
     stack.push(r);
     Ok(())
 }
 
-fn idiv(stack: &mut Vec<Val>) -> Result<(), String> {
-    let b = stack.pop().unwrap().to_f64()? as i64;
-    let a = stack.pop().unwrap().to_f64()? as i64;
-    let r = a / b;
-    let r = Val::Num(r as f64);
+// Truncating integer division, as opposed to FDiv's float division
+pub(crate) fn idiv(stack: &mut Vec<Val>) -> Result<(), String> {
+    let b = stack.pop().unwrap();
+    let a = stack.pop().unwrap();
+    let (a, b) = num2_loose(&a, &b);
+    let r = match (&a, &b) {
+        (Val::Int(a), Val::Int(b)) => {
+            if b.is_zero() {
+                return Err("Division by zero".to_string());
+            }
+            Val::Int(a / b)
+        }
+        _ => {
+            let b = b.to_f64()? as i64;
+            let a = a.to_f64()? as i64;
+            Val::Num((a / b) as f64)
+        }
+    };
     stack.push(r);
     Ok(())
 }
 
-fn mod_(stack: &mut Vec<Val>) -> Result<(), String> {
-    let b = stack.pop().unwrap().to_f64()?;
-    let a = stack.pop().unwrap().to_f64()?;
-    let r = Val::Num(a % b);
+pub(crate) fn mod_(stack: &mut Vec<Val>) -> Result<(), String> {
+    let b = stack.pop().unwrap();
+    let a = stack.pop().unwrap();
+    let (a, b) = num2_loose(&a, &b);
+    let r = match (&a, &b) {
+        (Val::Int(a), Val::Int(b)) => {
+            if b.is_zero() {
+                return Err("Division by zero".to_string());
+            }
+            Val::Int(a % b)
+        }
+        (Val::Num(a), Val::Num(b)) => Val::Num(a % b),
+        _ => return Err("Not numbers".to_string()),
+    };
     stack.push(r);
     Ok(())
 }
 
-fn bit_not(stack: &mut Vec<Val>) -> Result<(), String> {
-    let a = stack.pop().unwrap().to_f64()? as i64;
-    let r = !a;
-    let r = Val::Num(r as f64);
+pub(crate) fn bit_not(stack: &mut Vec<Val>) -> Result<(), String> {
+    let a = stack.pop().unwrap().num_loose();
+    let r = match a {
+        Val::Int(a) => Val::Int(!a),
+        Val::Num(a) => Val::Num(!(a as i64) as f64),
+        _ => return Err("Not a number".to_string()),
+    };
     stack.push(r);
     Ok(())
 }
 
-fn mul(stack: &mut Vec<Val>) -> Result<(), String> {
+pub(crate) fn mul(stack: &mut Vec<Val>) -> Result<(), String> {
     let b = stack.pop().unwrap();
     let a = stack.pop().unwrap();
     let (a, b) = num2_loose(&a, &b);
@@ -174,7 +408,11 @@ impl VM {
     pub fn new() -> Self {
         let mut vm = VM {
             rng: ChaCha20Rng::seed_from_u64(0),
+            clock: Box::new(SystemClock),
             vars: HashMap::new(),
+            frames: Vec::new(),
+            handlers: Vec::new(),
+            program: None,
         };
         // TODO: constants
         vm.register("inf", Val::Num(std::f64::INFINITY));
@@ -208,6 +446,63 @@ impl VM {
         self.vars.insert(name.to_string(), Val::funcv(f));
     }
 
+    // Resolves a name against globals/builtins. Locals and parameters are
+    // resolved at compile time to frame slots (LoadSlot/StoreSlot) instead,
+    // so by the time a Load/Call reaches the VM, `name` always names a
+    // global or builtin.
+    fn lookup(&self, name: &str) -> Option<Val> {
+        self.vars.get(name).cloned()
+    }
+
+    // Pushes a call frame for invoking `c` with the `n` already-evaluated
+    // arguments on top of `stack`, and returns the pc to jump to. Mirrors
+    // the arity check the native Func1/2/3 arms do below.
+    fn call_closure(
+        &mut self,
+        stack: &mut Vec<Val>,
+        c: &Rc<ClosureVal>,
+        n: usize,
+        return_pc: usize,
+    ) -> Result<usize, String> {
+        if n != c.params.len() {
+            return Err(format!("Expected {} args, received {}", c.params.len(), n));
+        }
+        let args = stack.split_off(stack.len() - n);
+        // Params fill the first slots, captured free variables the next
+        // `free.len()` slots (same order as `free`), and the rest are the
+        // function's own locals, uninitialized until their first Store
+        let mut locals = vec![Val::Null; c.slots];
+        for (i, a) in args.into_iter().enumerate() {
+            locals[i] = a;
+        }
+        for (i, a) in c.captured.iter().enumerate() {
+            locals[c.params.len() + i] = a.clone();
+        }
+        let stack_base = stack.len();
+        self.frames.push(Frame {
+            return_pc,
+            locals,
+            stack_base,
+        });
+        Ok(c.pc)
+    }
+
+    // Unwinds to the innermost active `try` handler, binding `msg` to its
+    // catch variable and truncating `stack` back to the depth it had when
+    // the handler was pushed. With no handler active, the error propagates
+    // out of `run` exactly as it did before `try`/`throw` existed.
+    fn raise(&mut self, stack: &mut Vec<Val>, pc: &mut usize, msg: String) -> Result<(), String> {
+        match self.handlers.pop() {
+            Some((target, len, name)) => {
+                stack.truncate(len);
+                self.vars.insert(name, Val::Str(msg));
+                *pc = target;
+                Ok(())
+            }
+            None => Err(msg),
+        }
+    }
+
     fn call1(&mut self, stack: &mut Vec<Val>, f: &Val, n: usize) -> Result<Val, String> {
         match f {
             Val::Func0(f) => {
@@ -250,24 +545,26 @@ impl VM {
                 let i = i.to_usize()?;
                 Ok(a.borrow().v[i].clone())
             }
-            Val::Str(s) => match n {
-                1 => {
-                    let i = stack.pop().unwrap();
-                    let i = i.to_isize()?;
-                    let i = slice_index(s.len(), i);
-                    let c = s.as_bytes()[i] as char;
-                    let s = c.to_string();
-                    Ok(Val::Str(s))
-                }
-                2 => {
-                    let j = stack.pop().unwrap();
-                    let i = stack.pop().unwrap();
-                    let (i, j) = slice_indexes(s.len(), i, j)?;
-                    let s = &s[i..j];
-                    Ok(Val::Str(s.to_string()))
+            Val::Str(s) => {
+                // Index by grapheme cluster (user-perceived character) rather
+                // than byte, so non-ASCII strings don't get sliced mid-codepoint
+                let gs: Vec<&str> = s.graphemes(true).collect();
+                match n {
+                    1 => {
+                        let i = stack.pop().unwrap();
+                        let i = i.to_isize()?;
+                        let i = slice_index(gs.len(), i);
+                        Ok(Val::Str(gs[i].to_string()))
+                    }
+                    2 => {
+                        let j = stack.pop().unwrap();
+                        let i = stack.pop().unwrap();
+                        let (i, j) = slice_indexes(gs.len(), i, j)?;
+                        Ok(Val::Str(gs[i..j].concat()))
+                    }
+                    _ => Err("String expects 1 or 2 indexes".to_string()),
                 }
-                _ => Err("String expects 1 or 2 indexes".to_string()),
-            },
+            }
             _ => Err("Called a non-function".to_string()),
         }
     }
@@ -285,26 +582,100 @@ impl VM {
         }
     }
 
+    // Invokes `f` with `args` and, unlike `call1`, also handles `Val::Closure`
+    // by pushing a call frame and driving `exec` until it returns. This is
+    // what lets native iterator builtins (map/filter/reduce/...) call back
+    // into Verbena-level callbacks the same way `Inst::Call` does.
+    pub fn call_value(&mut self, f: &Val, args: Vec<Val>) -> Result<Val, String> {
+        match f {
+            Val::Closure(c) => {
+                let c = c.clone();
+                let program = self
+                    .program
+                    .clone()
+                    .ok_or_else(|| "No program is running".to_string())?;
+                let n = args.len();
+                let mut stack = args;
+                let pc = self.call_closure(&mut stack, &c, n, 0)?;
+                let target_depth = self.frames.len();
+                self.exec(&program, &mut stack, pc, target_depth)
+            }
+            _ => {
+                let n = args.len();
+                let mut stack = args;
+                self.call1(&mut stack, f, n)
+            }
+        }
+    }
+
     pub fn run(&mut self, program: Program) -> Result<Val, String> {
+        let program = Rc::new(program);
+        self.program = Some(program.clone());
         let mut stack = Vec::<Val>::new();
-        let mut pc = 0usize;
+        // target_depth 0 never triggers early return (frames.len() can't go
+        // negative), so top-level execution keeps its original behavior
+        self.exec(&program, &mut stack, 0, 0)
+    }
+
+    // The interpreter's dispatch loop, shared by top-level `run()` and by
+    // `call_value`'s re-entrant invocation of closures: stops either when
+    // `pc` runs past the end of `program` (top level) or as soon as a
+    // `Return`/`Exit` pops the call frame stack back below `target_depth`
+    // (a nested call made on `call_value`'s behalf).
+    fn exec(&mut self, program: &Program, stack: &mut Vec<Val>, start_pc: usize, target_depth: usize) -> Result<Val, String> {
+        let mut pc = start_pc;
         while pc < program.code.len() {
             let ec = &program.ecs[pc];
             // TODO: refactor err
             match &program.code[pc] {
                 Inst::Call(name, n) => {
-                    let f = match self.vars.get(name) {
-                        Some(a) => a.clone(),
+                    let f = match self.lookup(name) {
+                        Some(a) => a,
                         None => {
-                            return Err(error(ec, format!("'{}' is not defined", name)));
+                            self.raise(stack, &mut pc, error(ec, format!("'{}' is not defined", name)))?;
+                            continue;
+                        }
+                    };
+                    if let Val::Closure(c) = &f {
+                        pc = match self.call_closure(stack, c, *n, pc + 1) {
+                            Ok(p) => p,
+                            Err(s) => {
+                                self.raise(stack, &mut pc, error(ec, s))?;
+                                continue;
+                            }
+                        };
+                        continue;
+                    }
+                    let r = match self.call(stack, ec, &f, *n) {
+                        Ok(r) => r,
+                        Err(s) => {
+                            self.raise(stack, &mut pc, s)?;
+                            continue;
                         }
                     };
-                    let r = self.call(&mut stack, ec, &f, *n)?;
                     stack.push(r);
                 }
                 Inst::CallIndirect(n) => {
                     let f = stack[stack.len() - 1 - n].clone();
-                    let r = self.call(&mut stack, ec, &f, *n)?;
+                    if let Val::Closure(c) = &f {
+                        let i = stack.len() - 1 - n;
+                        stack.remove(i);
+                        pc = match self.call_closure(stack, c, *n, pc + 1) {
+                            Ok(p) => p,
+                            Err(s) => {
+                                self.raise(stack, &mut pc, error(ec, s))?;
+                                continue;
+                            }
+                        };
+                        continue;
+                    }
+                    let r = match self.call(stack, ec, &f, *n) {
+                        Ok(r) => r,
+                        Err(s) => {
+                            self.raise(stack, &mut pc, s)?;
+                            continue;
+                        }
+                    };
                     let i = stack.len() - 1;
                     stack[i] = r;
                 }
@@ -314,44 +685,47 @@ impl VM {
                 Inst::Pop => {
                     stack.pop().unwrap();
                 }
-                Inst::Add => {
-                    // TODO: fn?
-                    let b = stack.pop().unwrap();
-                    let a = stack.pop().unwrap();
-                    let (a, b) = num2_loose(&a, &b);
-                    let r = match (&a, &b) {
-                        (Val::Int(a), Val::Int(b)) => Val::Int(a + b),
-                        (Val::Num(a), Val::Num(b)) => Val::Num(a + b),
-                        _ => {
-                            let a = a.to_string();
-                            let b = b.to_string();
-                            let mut r = String::with_capacity(a.len() + b.len());
-                            r.push_str(&a);
-                            r.push_str(&b);
-                            Val::Str(r)
-                        }
-                    };
-                    stack.push(r);
-                }
-                Inst::Sub => match sub(&mut stack) {
+                Inst::Add => match add(stack) {
                     Ok(_) => {}
-                    Err(s) => return Err(format!("{}: {}", ec, s)),
+                    Err(s) => {
+                        self.raise(stack, &mut pc, format!("{}: {}", ec, s))?;
+                        continue;
+                    }
                 },
-                Inst::Mul => match mul(&mut stack) {
+                Inst::Sub => match sub(stack) {
                     Ok(_) => {}
-                    Err(s) => return Err(format!("{}: {}", ec, s)),
+                    Err(s) => {
+                        self.raise(stack, &mut pc, format!("{}: {}", ec, s))?;
+                        continue;
+                    }
                 },
-                Inst::IDiv => match idiv(&mut stack) {
+                Inst::Mul => match mul(stack) {
                     Ok(_) => {}
-                    Err(s) => return Err(format!("{}: {}", ec, s)),
+                    Err(s) => {
+                        self.raise(stack, &mut pc, format!("{}: {}", ec, s))?;
+                        continue;
+                    }
+                },
+                Inst::IDiv => match idiv(stack) {
+                    Ok(_) => {}
+                    Err(s) => {
+                        self.raise(stack, &mut pc, format!("{}: {}", ec, s))?;
+                        continue;
+                    }
                 },
-                Inst::FDiv => match fdiv(&mut stack) {
+                Inst::FDiv => match fdiv(stack) {
                     Ok(_) => {}
-                    Err(s) => return Err(format!("{}: {}", ec, s)),
+                    Err(s) => {
+                        self.raise(stack, &mut pc, format!("{}: {}", ec, s))?;
+                        continue;
+                    }
                 },
-                Inst::Mod => match mod_(&mut stack) {
+                Inst::Mod => match mod_(stack) {
                     Ok(_) => {}
-                    Err(s) => return Err(format!("{}: {}", ec, s)),
+                    Err(s) => {
+                        self.raise(stack, &mut pc, format!("{}: {}", ec, s))?;
+                        continue;
+                    }
                 },
                 Inst::Eq => {
                     let b = stack.pop().unwrap();
@@ -389,42 +763,66 @@ impl VM {
                     let r = Val::from_bool(le_loose(&b, &a));
                     stack.push(r);
                 }
-                Inst::Shl => match shl(&mut stack) {
+                Inst::Shl => match shl(stack) {
                     Ok(_) => {}
-                    Err(s) => return Err(format!("{}: {}", ec, s)),
+                    Err(s) => {
+                        self.raise(stack, &mut pc, format!("{}: {}", ec, s))?;
+                        continue;
+                    }
                 },
-                Inst::Shr => match shr(&mut stack) {
+                Inst::Shr => match shr(stack) {
                     Ok(_) => {}
-                    Err(s) => return Err(format!("{}: {}", ec, s)),
+                    Err(s) => {
+                        self.raise(stack, &mut pc, format!("{}: {}", ec, s))?;
+                        continue;
+                    }
                 },
-                Inst::BitAnd => match bit_and(&mut stack) {
+                Inst::BitAnd => match bit_and(stack) {
                     Ok(_) => {}
-                    Err(s) => return Err(format!("{}: {}", ec, s)),
+                    Err(s) => {
+                        self.raise(stack, &mut pc, format!("{}: {}", ec, s))?;
+                        continue;
+                    }
                 },
-                Inst::BitXor => match bit_xor(&mut stack) {
+                Inst::BitXor => match bit_xor(stack) {
                     Ok(_) => {}
-                    Err(s) => return Err(format!("{}: {}", ec, s)),
+                    Err(s) => {
+                        self.raise(stack, &mut pc, format!("{}: {}", ec, s))?;
+                        continue;
+                    }
                 },
-                Inst::BitOr => match bit_or(&mut stack) {
+                Inst::BitOr => match bit_or(stack) {
                     Ok(_) => {}
-                    Err(s) => return Err(format!("{}: {}", ec, s)),
+                    Err(s) => {
+                        self.raise(stack, &mut pc, format!("{}: {}", ec, s))?;
+                        continue;
+                    }
                 },
-                Inst::Pow => match pow(&mut stack) {
+                Inst::Pow => match pow(stack) {
                     Ok(_) => {}
-                    Err(s) => return Err(format!("{}: {}", ec, s)),
+                    Err(s) => {
+                        self.raise(stack, &mut pc, format!("{}: {}", ec, s))?;
+                        continue;
+                    }
                 },
-                Inst::Neg => match neg(&mut stack) {
+                Inst::Neg => match neg(stack) {
                     Ok(_) => {}
-                    Err(s) => return Err(format!("{}: {}", ec, s)),
+                    Err(s) => {
+                        self.raise(stack, &mut pc, format!("{}: {}", ec, s))?;
+                        continue;
+                    }
                 },
                 Inst::Not => {
                     let a = stack.pop().unwrap();
                     let r = Val::from_bool(!a.truth());
                     stack.push(r);
                 }
-                Inst::BitNot => match bit_not(&mut stack) {
+                Inst::BitNot => match bit_not(stack) {
                     Ok(_) => {}
-                    Err(s) => return Err(format!("{}: {}", ec, s)),
+                    Err(s) => {
+                        self.raise(stack, &mut pc, format!("{}: {}", ec, s))?;
+                        continue;
+                    }
                 },
                 Inst::BrFalse(target) => {
                     let cond = stack.pop().unwrap();
@@ -443,7 +841,8 @@ impl VM {
                 Inst::Assert(msg) => {
                     let cond = stack.pop().unwrap();
                     if !cond.truth() {
-                        return Err(error(ec, msg));
+                        self.raise(stack, &mut pc, error(ec, msg))?;
+                        continue;
                     }
                 }
                 Inst::DupBrFalse(target) => {
@@ -461,41 +860,169 @@ impl VM {
                     }
                 }
                 Inst::Load(name) => {
-                    let a = match self.vars.get(name) {
+                    let a = match self.lookup(name) {
                         Some(a) => a,
                         None => {
-                            return Err(error(ec, format!("'{}' is not defined", name)));
+                            self.raise(stack, &mut pc, error(ec, format!("'{}' is not defined", name)))?;
+                            continue;
                         }
                     };
-                    stack.push(a.clone());
+                    stack.push(a);
                 }
                 Inst::StoreAt => {
                     let x = stack.pop().unwrap();
                     let i = stack.pop().unwrap();
                     let a = stack.pop().unwrap();
-                    let i = i.to_usize()?;
+                    let i = match i.to_usize() {
+                        Ok(i) => i,
+                        Err(s) => {
+                            self.raise(stack, &mut pc, error(ec, s))?;
+                            continue;
+                        }
+                    };
                     match a {
                         Val::List(a) => {
                             a.borrow_mut().v[i] = x.clone();
                         }
-                        _ => return Err(error(ec, "Not a list".to_string())),
+                        _ => {
+                            self.raise(stack, &mut pc, error(ec, "Not a list".to_string()))?;
+                            continue;
+                        }
                     };
                     stack.push(x);
                 }
+                Inst::MakeObject(n) => {
+                    // Pairs are pushed key0, value0, key1, value1, ..., so
+                    // popping them off comes back in reverse; collect first
+                    // and insert in reverse-of-reverse (i.e. source) order
+                    // so the Object's insertion order matches the literal
+                    let mut pairs = Vec::with_capacity(*n);
+                    for _ in 0..*n {
+                        let value = stack.pop().unwrap();
+                        let key = stack.pop().unwrap();
+                        pairs.push((key, value));
+                    }
+                    let mut obj = Object::new();
+                    for (key, value) in pairs.into_iter().rev() {
+                        obj.insert(key.to_string(), value);
+                    }
+                    stack.push(Val::object(obj));
+                }
+                Inst::GetField(_ec) => {
+                    let key = stack.pop().unwrap();
+                    let a = stack.pop().unwrap();
+                    match a {
+                        Val::Object(o) => {
+                            let key = key.to_string();
+                            let v = o.borrow().get(&key).cloned();
+                            match v {
+                                Some(v) => stack.push(v),
+                                None => {
+                                    self.raise(stack, &mut pc, error(ec, format!("Key '{}' not found", key)))?;
+                                    continue;
+                                }
+                            }
+                        }
+                        _ => {
+                            self.raise(stack, &mut pc, error(ec, "Not an object".to_string()))?;
+                            continue;
+                        }
+                    }
+                }
+                Inst::SetField(_ec) => {
+                    let x = stack.pop().unwrap();
+                    let key = stack.pop().unwrap();
+                    let a = stack.pop().unwrap();
+                    match a {
+                        Val::Object(o) => {
+                            let key = key.to_string();
+                            o.borrow_mut().insert(key, x.clone());
+                        }
+                        _ => {
+                            self.raise(stack, &mut pc, error(ec, "Not an object".to_string()))?;
+                            continue;
+                        }
+                    };
+                    stack.push(x);
+                }
+                Inst::MakeClosure(pc_target, slots, params, free) => {
+                    // Captured values are pushed by the preceding Load/LoadSlot
+                    // instructions in `free` order, so they come off the stack
+                    // in that same order via split_off, unlike MakeObject's
+                    // reversed pairs
+                    let captured = stack.split_off(stack.len() - free.len());
+                    stack.push(Val::closure(params.clone(), free.clone(), captured, *pc_target, *slots));
+                }
                 Inst::Store(name) => {
+                    // Locals/params are StoreSlot by the time they reach here
+                    // (see `lookup`), so this always writes a global
                     let a = stack.last().unwrap().clone();
                     self.vars.insert(name.clone(), a);
                 }
+                Inst::LoadSlot(i) => {
+                    let a = self.frames.last().unwrap().locals[*i].clone();
+                    stack.push(a);
+                }
+                Inst::StoreSlot(i) => {
+                    let a = stack.last().unwrap().clone();
+                    self.frames.last_mut().unwrap().locals[*i] = a;
+                }
                 Inst::Br(target) => {
                     pc = *target;
                     continue;
                 }
-                Inst::Return => {
-                    return Ok(Val::Int(BigInt::zero()));
+                Inst::PushHandler(target, name) => {
+                    self.handlers.push((*target, stack.len(), name.clone()));
                 }
+                Inst::PopHandler => {
+                    self.handlers.pop();
+                }
+                Inst::Throw => {
+                    let a = stack.pop().unwrap();
+                    match self.handlers.pop() {
+                        Some((target, len, name)) => {
+                            stack.truncate(len);
+                            self.vars.insert(name, a);
+                            pc = target;
+                            continue;
+                        }
+                        None => {
+                            return Err(error(ec, a.to_string()));
+                        }
+                    }
+                }
+                Inst::Return => match self.frames.pop() {
+                    Some(frame) => {
+                        let r = stack.pop().unwrap_or(Val::Int(BigInt::zero()));
+                        stack.truncate(frame.stack_base);
+                        stack.push(r.clone());
+                        // A call frame pushed on `call_value`'s behalf (not by
+                        // this loop's own Call/CallIndirect) returns here
+                        // instead of resuming at `frame.return_pc`
+                        if self.frames.len() < target_depth {
+                            return Ok(r);
+                        }
+                        pc = frame.return_pc;
+                        continue;
+                    }
+                    None => {
+                        return Ok(Val::Int(BigInt::zero()));
+                    }
+                },
                 Inst::Exit => {
                     let a = stack.pop().unwrap();
-                    return Ok(a);
+                    match self.frames.pop() {
+                        Some(frame) => {
+                            stack.truncate(frame.stack_base);
+                            stack.push(a.clone());
+                            if self.frames.len() < target_depth {
+                                return Ok(a);
+                            }
+                            pc = frame.return_pc;
+                            continue;
+                        }
+                        None => return Ok(a),
+                    }
                 }
             }
             pc += 1;
@@ -0,0 +1,78 @@
+use verbena::*;
+
+const FILE: &str = "test";
+
+#[test]
+fn literal_and_bind_arms() {
+    let text = "case x\n| 1\n  print a\n| n\n  print n\nend\n";
+    let v = parse_str(FILE, &text).unwrap();
+    assert_eq!(v.len(), 1);
+    match &v[0] {
+        Stmt::Case(_, subject, arms) => {
+            match subject {
+                Expr::Atom(_, s) => assert_eq!(s, "x"),
+                _ => panic!("Expected atom subject"),
+            }
+            assert_eq!(arms.len(), 2);
+
+            let (patterns, body) = &arms[0];
+            assert_eq!(patterns.len(), 1);
+            match &patterns[0] {
+                Pattern::Literal(Expr::Atom(_, s)) => assert_eq!(s, "1"),
+                _ => panic!("Expected a literal pattern"),
+            }
+            assert_eq!(body.len(), 1);
+
+            let (patterns, _) = &arms[1];
+            match &patterns[0] {
+                Pattern::Bind(s) => assert_eq!(s, "n"),
+                _ => panic!("Expected a bind pattern"),
+            }
+        }
+        _ => panic!("Expected a case statement"),
+    }
+}
+
+#[test]
+fn wildcard_list_and_else_arms() {
+    let text = "case x\n| [a, b, ...rest]\n  print a\n| _\n  print 0\nelse\n  print 1\nend\n";
+    let v = parse_str(FILE, &text).unwrap();
+    match &v[0] {
+        Stmt::Case(_, _, arms) => {
+            assert_eq!(arms.len(), 3);
+
+            match &arms[0].0[0] {
+                Pattern::List(elems, rest) => {
+                    assert_eq!(elems.len(), 2);
+                    assert!(rest.is_some());
+                }
+                _ => panic!("Expected a list pattern"),
+            }
+
+            match &arms[1].0[0] {
+                Pattern::Wildcard => {}
+                _ => panic!("Expected a wildcard pattern"),
+            }
+
+            // The `else` arm has no patterns at all
+            assert!(arms[2].0.is_empty());
+        }
+        _ => panic!("Expected a case statement"),
+    }
+}
+
+#[test]
+fn guarded_pattern() {
+    let text = "case x\n| n if n > 0\n  print n\nend\n";
+    let v = parse_str(FILE, &text).unwrap();
+    match &v[0] {
+        Stmt::Case(_, _, arms) => match &arms[0].0[0] {
+            Pattern::Guard(pat, cond) => {
+                assert!(matches!(**pat, Pattern::Bind(_)));
+                assert!(matches!(cond, Expr::Infix(_, op, _, _) if op == ">"));
+            }
+            _ => panic!("Expected a guarded pattern"),
+        },
+        _ => panic!("Expected a case statement"),
+    }
+}
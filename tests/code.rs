@@ -0,0 +1,98 @@
+use verbena::*;
+
+#[test]
+fn test_round_trip_simple() {
+    let fd = FuncDef {
+        insts: vec![
+            Inst::Const(Val::Num(1.0)),
+            Inst::Const(Val::Num(2.0)),
+            Inst::Add,
+            Inst::Exit,
+        ],
+        ecs: vec![
+            ErrorContext {
+                file: "a.vb".to_string(),
+                line: 1,
+            },
+            ErrorContext {
+                file: "a.vb".to_string(),
+                line: 1,
+            },
+            ErrorContext {
+                file: "a.vb".to_string(),
+                line: 1,
+            },
+            ErrorContext {
+                file: "a.vb".to_string(),
+                line: 2,
+            },
+        ],
+    };
+
+    let bytes = fd.to_bytes().unwrap();
+    let back = FuncDef::from_bytes(&bytes).unwrap();
+
+    assert_eq!(format!("{:?}", back), format!("{:?}", fd));
+}
+
+#[test]
+fn test_round_trip_strings_and_branches() {
+    let fd = FuncDef {
+        insts: vec![
+            Inst::Const(Val::Str("hello".to_string())),
+            Inst::LoadGlobal("x".to_string()),
+            Inst::StoreGlobal("y".to_string()),
+            Inst::BrTrue(0),
+            Inst::Br(3),
+            Inst::Call(2),
+            Inst::Return,
+        ],
+        ecs: vec![
+            ErrorContext {
+                file: "b.vb".to_string(),
+                line: 1,
+            };
+            7
+        ],
+    };
+
+    let bytes = fd.to_bytes().unwrap();
+    let back = FuncDef::from_bytes(&bytes).unwrap();
+
+    assert_eq!(format!("{:?}", back), format!("{:?}", fd));
+}
+
+#[test]
+fn test_rejects_foreign_bytes() {
+    let err = FuncDef::from_bytes(b"not bytecode").unwrap_err();
+    assert!(err.contains("Not a verbena bytecode file"));
+}
+
+#[test]
+fn test_rejects_truncated_bytes() {
+    let fd = FuncDef {
+        insts: vec![Inst::Const(Val::Num(1.0)), Inst::Exit],
+        ecs: vec![
+            ErrorContext {
+                file: "a.vb".to_string(),
+                line: 1,
+            };
+            2
+        ],
+    };
+    let bytes = fd.to_bytes().unwrap();
+    let err = FuncDef::from_bytes(&bytes[..bytes.len() - 2]).unwrap_err();
+    assert!(err.contains("Truncated"));
+}
+
+#[test]
+fn test_rejects_unserializable_values() {
+    let fd = FuncDef {
+        insts: vec![Inst::Const(Val::func0(|_vm| Ok(Val::Null)))],
+        ecs: vec![ErrorContext {
+            file: "a.vb".to_string(),
+            line: 1,
+        }],
+    };
+    assert!(fd.to_bytes().is_err());
+}
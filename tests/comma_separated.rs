@@ -0,0 +1,46 @@
+use verbena::*;
+
+const FILE: &str = "test";
+
+#[test]
+fn call_arguments_that_are_themselves_list_literals_do_not_bleed_together() {
+    // `f([1, 2], [3, 4])` has two levels of comma-separated lists: the call
+    // arguments, and each argument's own list items. `comma_separated` is
+    // reused for both, and a single `expr()` call parses exactly one item at
+    // each level - the inner `]`/`)` closes its own list before the outer
+    // loop ever sees the comma between the two arguments.
+    let text = "f([1, 2], [3, 4])";
+    let v = parse_str(FILE, text).unwrap();
+    match &v[0] {
+        Stmt::Expr(_, Expr::Call(_, _, args)) => {
+            assert_eq!(args.len(), 2);
+            match &args[0] {
+                Expr::List(_, items) => assert_eq!(items.len(), 2),
+                _ => panic!("Expected a list literal"),
+            }
+            match &args[1] {
+                Expr::List(_, items) => assert_eq!(items.len(), 2),
+                _ => panic!("Expected a list literal"),
+            }
+        }
+        _ => panic!("Expected a call expression"),
+    }
+}
+
+#[test]
+fn list_literal_containing_call_expressions_does_not_bleed_together() {
+    let text = "[f(1, 2), g(3, 4)]";
+    let v = parse_str(FILE, text).unwrap();
+    match &v[0] {
+        Stmt::Expr(_, Expr::List(_, items)) => {
+            assert_eq!(items.len(), 2);
+            for item in items {
+                match item {
+                    Expr::Call(_, _, args) => assert_eq!(args.len(), 2),
+                    _ => panic!("Expected a call expression"),
+                }
+            }
+        }
+        _ => panic!("Expected a list literal"),
+    }
+}
@@ -0,0 +1,35 @@
+use verbena::*;
+
+const FILE: &str = "test";
+
+#[test]
+fn add_assign_desugars_to_assign_of_infix() {
+    let text = "x += 1";
+    let v = parse_str(FILE, &text).unwrap();
+    match &v[0] {
+        Stmt::Expr(_, Expr::Assign(_, lhs, rhs)) => {
+            assert!(matches!(lhs.as_ref(), Expr::Atom(_, s) if s == "x"));
+            match rhs.as_ref() {
+                Expr::Infix(_, op, a, b) => {
+                    assert_eq!(op, "+");
+                    assert!(matches!(a.as_ref(), Expr::Atom(_, s) if s == "x"));
+                    assert!(matches!(b.as_ref(), Expr::Atom(_, s) if s == "1"));
+                }
+                _ => panic!("Expected an infix expression"),
+            }
+        }
+        _ => panic!("Expected an assignment"),
+    }
+}
+
+#[test]
+fn full_compound_assign_operator_set_parses() {
+    // One statement per compound operator; if any token/desugar pairing is
+    // missing or miswired this fails to parse at all
+    let text = "x += 1\nx -= 1\nx *= 1\nx /= 1\nx %= 1\nx **= 1\nx &= 1\nx |= 1\nx ^= 1\nx <<= 1\nx >>= 1\nx >>>= 1\n";
+    let v = parse_str(FILE, &text).unwrap();
+    assert_eq!(v.len(), 12);
+    for stmt in &v {
+        assert!(matches!(stmt, Stmt::Expr(_, Expr::Assign(..))));
+    }
+}
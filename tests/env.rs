@@ -18,14 +18,14 @@ fn test_env_get_set_same_level() {
     let mut env = Env::new(None, 3);
 
     // Set values
-    env.set(0, 0, Val::Num(42.0));
-    env.set(0, 1, Val::Str("hello".to_string()));
-    env.set(0, 2, Val::True);
+    env.set(0, 0, Val::Num(42.0)).unwrap();
+    env.set(0, 1, Val::Str("hello".to_string())).unwrap();
+    env.set(0, 2, Val::True).unwrap();
 
     // Get values and verify
-    assert_eq!(env.get(0, 0), Val::Num(42.0));
-    assert_eq!(env.get(0, 1), Val::Str("hello".to_string()));
-    assert_eq!(env.get(0, 2), Val::True);
+    assert_eq!(env.get(0, 0).unwrap(), Val::Num(42.0));
+    assert_eq!(env.get(0, 1).unwrap(), Val::Str("hello".to_string()));
+    assert_eq!(env.get(0, 2).unwrap(), Val::True);
 }
 
 #[test]
@@ -34,60 +34,61 @@ fn test_env_get_set_nested_one_level() {
     let outer_env = Rc::new(RefCell::new(Env::new(None, 2)));
 
     // Set values in outer env
-    outer_env.borrow_mut().set(0, 0, Val::Num(100.0));
+    outer_env.borrow_mut().set(0, 0, Val::Num(100.0)).unwrap();
     outer_env
         .borrow_mut()
-        .set(0, 1, Val::Str("outer".to_string()));
+        .set(0, 1, Val::Str("outer".to_string()))
+        .unwrap();
 
     // Create inner environment
     let mut inner_env = Env::new(Some(Rc::clone(&outer_env)), 1);
-    inner_env.set(0, 0, Val::False);
+    inner_env.set(0, 0, Val::False).unwrap();
 
     // Get values from inner env (level 0)
-    assert_eq!(inner_env.get(0, 0), Val::False);
+    assert_eq!(inner_env.get(0, 0).unwrap(), Val::False);
 
     // Get values from outer env (level 1)
-    assert_eq!(inner_env.get(1, 0), Val::Num(100.0));
-    assert_eq!(inner_env.get(1, 1), Val::Str("outer".to_string()));
+    assert_eq!(inner_env.get(1, 0).unwrap(), Val::Num(100.0));
+    assert_eq!(inner_env.get(1, 1).unwrap(), Val::Str("outer".to_string()));
 
     // Modify outer env through inner env
-    inner_env.set(1, 0, Val::Num(200.0));
-    assert_eq!(inner_env.get(1, 0), Val::Num(200.0));
-    assert_eq!(outer_env.borrow().get(0, 0), Val::Num(200.0));
+    inner_env.set(1, 0, Val::Num(200.0)).unwrap();
+    assert_eq!(inner_env.get(1, 0).unwrap(), Val::Num(200.0));
+    assert_eq!(outer_env.borrow().get(0, 0).unwrap(), Val::Num(200.0));
 }
 
 #[test]
 fn test_env_get_set_nested_multiple_levels() {
     // Create level 2 (outermost) environment
     let env_lvl2 = Rc::new(RefCell::new(Env::new(None, 1)));
-    env_lvl2.borrow_mut().set(0, 0, Val::Num(2.0));
+    env_lvl2.borrow_mut().set(0, 0, Val::Num(2.0)).unwrap();
 
     // Create level 1 environment
     let env_lvl1 = Rc::new(RefCell::new(Env::new(Some(Rc::clone(&env_lvl2)), 1)));
-    env_lvl1.borrow_mut().set(0, 0, Val::Num(1.0));
+    env_lvl1.borrow_mut().set(0, 0, Val::Num(1.0)).unwrap();
 
     // Create level 0 (innermost) environment
     let mut env_lvl0 = Env::new(Some(Rc::clone(&env_lvl1)), 1);
-    env_lvl0.set(0, 0, Val::Num(0.0));
+    env_lvl0.set(0, 0, Val::Num(0.0)).unwrap();
 
     // Get values from different levels
-    assert_eq!(env_lvl0.get(0, 0), Val::Num(0.0)); // Level 0
-    assert_eq!(env_lvl0.get(1, 0), Val::Num(1.0)); // Level 1
-    assert_eq!(env_lvl0.get(2, 0), Val::Num(2.0)); // Level 2
+    assert_eq!(env_lvl0.get(0, 0).unwrap(), Val::Num(0.0)); // Level 0
+    assert_eq!(env_lvl0.get(1, 0).unwrap(), Val::Num(1.0)); // Level 1
+    assert_eq!(env_lvl0.get(2, 0).unwrap(), Val::Num(2.0)); // Level 2
 
     // Modify values at different levels
-    env_lvl0.set(0, 0, Val::Num(10.0)); // Modify level 0
-    env_lvl0.set(1, 0, Val::Num(11.0)); // Modify level 1
-    env_lvl0.set(2, 0, Val::Num(12.0)); // Modify level 2
+    env_lvl0.set(0, 0, Val::Num(10.0)).unwrap(); // Modify level 0
+    env_lvl0.set(1, 0, Val::Num(11.0)).unwrap(); // Modify level 1
+    env_lvl0.set(2, 0, Val::Num(12.0)).unwrap(); // Modify level 2
 
     // Verify changes
-    assert_eq!(env_lvl0.get(0, 0), Val::Num(10.0));
-    assert_eq!(env_lvl0.get(1, 0), Val::Num(11.0));
-    assert_eq!(env_lvl0.get(2, 0), Val::Num(12.0));
+    assert_eq!(env_lvl0.get(0, 0).unwrap(), Val::Num(10.0));
+    assert_eq!(env_lvl0.get(1, 0).unwrap(), Val::Num(11.0));
+    assert_eq!(env_lvl0.get(2, 0).unwrap(), Val::Num(12.0));
 
     // Verify changes in the original environments
-    assert_eq!(env_lvl1.borrow().get(0, 0), Val::Num(11.0));
-    assert_eq!(env_lvl2.borrow().get(0, 0), Val::Num(12.0));
+    assert_eq!(env_lvl1.borrow().get(0, 0).unwrap(), Val::Num(11.0));
+    assert_eq!(env_lvl2.borrow().get(0, 0).unwrap(), Val::Num(12.0));
 }
 
 #[test]
@@ -96,55 +97,49 @@ fn test_env_with_complex_values() {
     let mut env = Env::new(None, 5);
 
     // Set different types of values
-    env.set(0, 0, Val::Num(3.14));
-    env.set(0, 1, Val::Str("Complex".to_string()));
-    env.set(0, 2, Val::True);
-    env.set(0, 3, Val::False);
-    env.set(0, 4, Val::Null);
+    env.set(0, 0, Val::Num(3.14)).unwrap();
+    env.set(0, 1, Val::Str("Complex".to_string())).unwrap();
+    env.set(0, 2, Val::True).unwrap();
+    env.set(0, 3, Val::False).unwrap();
+    env.set(0, 4, Val::Null).unwrap();
 
     // Verify all types are stored and retrieved correctly
-    assert_eq!(env.get(0, 0), Val::Num(3.14));
-    assert_eq!(env.get(0, 1), Val::Str("Complex".to_string()));
-    assert_eq!(env.get(0, 2), Val::True);
-    assert_eq!(env.get(0, 3), Val::False);
-    assert_eq!(env.get(0, 4), Val::Null);
+    assert_eq!(env.get(0, 0).unwrap(), Val::Num(3.14));
+    assert_eq!(env.get(0, 1).unwrap(), Val::Str("Complex".to_string()));
+    assert_eq!(env.get(0, 2).unwrap(), Val::True);
+    assert_eq!(env.get(0, 3).unwrap(), Val::False);
+    assert_eq!(env.get(0, 4).unwrap(), Val::Null);
 
     // Test overwriting values with different types
-    env.set(0, 0, Val::Str("Overwritten".to_string()));
-    env.set(0, 1, Val::Num(42.0));
+    env.set(0, 0, Val::Str("Overwritten".to_string())).unwrap();
+    env.set(0, 1, Val::Num(42.0)).unwrap();
 
-    assert_eq!(env.get(0, 0), Val::Str("Overwritten".to_string()));
-    assert_eq!(env.get(0, 1), Val::Num(42.0));
+    assert_eq!(env.get(0, 0).unwrap(), Val::Str("Overwritten".to_string()));
+    assert_eq!(env.get(0, 1).unwrap(), Val::Num(42.0));
 }
 
 #[test]
-#[should_panic(expected = "index out of bounds")]
 fn test_env_out_of_bounds_get() {
     let env = Env::new(None, 1);
-    // This should panic because there's only one element at index 0
-    let _ = env.get(0, 1);
+    // Out of range now returns an error instead of panicking
+    assert!(env.get(0, 1).is_err());
 }
 
 #[test]
-#[should_panic(expected = "index out of bounds")]
 fn test_env_out_of_bounds_set() {
     let mut env = Env::new(None, 1);
-    // This should panic because there's only one element at index 0
-    env.set(0, 1, Val::Null);
+    assert!(env.set(0, 1, Val::Null).is_err());
 }
 
 #[test]
-#[should_panic(expected = "called `Option::unwrap()` on a `None` value")]
 fn test_env_invalid_level_get() {
     let env = Env::new(None, 1);
-    // This should panic because there's no outer environment at level 1
-    let _ = env.get(1, 0);
+    // There's no outer environment, so level 1 returns an error instead of panicking
+    assert!(env.get(1, 0).is_err());
 }
 
 #[test]
-#[should_panic(expected = "called `Option::unwrap()` on a `None` value")]
 fn test_env_invalid_level_set() {
     let mut env = Env::new(None, 1);
-    // This should panic because there's no outer environment at level 1
-    env.set(1, 0, Val::Null);
+    assert!(env.set(1, 0, Val::Null).is_err());
 }
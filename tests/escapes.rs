@@ -0,0 +1,81 @@
+use verbena::*;
+
+const FILE: &str = "test";
+
+#[test]
+fn recognized_escapes_parse() {
+    let text = r#"x = "\n\t\r\0\\\"\'\x41A\u{1F600}""#;
+    assert!(parse_str(FILE, text).is_ok());
+}
+
+#[test]
+fn js_escapes_that_predate_validation_still_parse() {
+    // \b \f \v \/ were all silently accepted (passed straight through to
+    // the JS compiler) before escape validation existed; rejecting them
+    // now would break any program that already used them
+    let text = r#"x = "\b\f\v\/path""#;
+    assert!(parse_str(FILE, text).is_ok());
+}
+
+#[test]
+fn out_of_range_unicode_code_point_is_lex_error() {
+    let text = r#"x = "\u{110000}""#;
+    let r = parse_str(FILE, text);
+    assert!(r.is_err());
+}
+
+#[test]
+fn unrecognized_escape_is_lex_error() {
+    let text = r#"x = "\q""#;
+    let r = parse_str(FILE, text);
+    assert!(r.is_err());
+}
+
+#[test]
+fn truncated_hex_escape_at_eof_is_lex_error() {
+    let text = "x = \"\\x4";
+    let r = parse_str(FILE, text);
+    assert!(r.is_err());
+}
+
+#[test]
+fn truncated_unicode_escape_at_eof_is_lex_error() {
+    let text = "x = \"\\u00";
+    let r = parse_str(FILE, text);
+    assert!(r.is_err());
+}
+
+#[test]
+fn truncated_hex_escape_no_trailing_char_eof() {
+    let text = "x = \"\\x41";
+    let r = parse_str(FILE, text);
+    assert!(r.is_err());
+}
+
+#[test]
+fn empty_unicode_braces_is_lex_error() {
+    let text = r#"x = "\u{}""#;
+    let r = parse_str(FILE, text);
+    assert!(r.is_err());
+}
+
+#[test]
+fn invalid_escape_does_not_hang_recovering_parse() {
+    // An invalid escape used to leave the rest of the string (which may
+    // itself contain quotes/braces) un-consumed, so it got re-lexed as
+    // unrelated tokens; in recovery mode (what parse_str always runs in)
+    // that could cascade into a stale token that resynchronize() never
+    // advances past, hanging forever instead of returning errors
+    let text = r#"x = "\u{}""#;
+    let r = parse_str(FILE, text);
+    assert!(r.is_err());
+}
+
+#[test]
+fn unknown_character_does_not_hang_recovering_parse() {
+    // Same class of bug as above, but for the lexer's catch-all "no token
+    // matches this character" path rather than a malformed string escape
+    let text = "x = \\";
+    let r = parse_str(FILE, text);
+    assert!(r.is_err());
+}
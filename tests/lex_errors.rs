@@ -1,14 +1,27 @@
 use verbena::*;
 
+const FILE: &str = "test";
+
 #[test]
 fn ats() {
     let text = "@@@@@";
-    let r = parse(text);
+    let r = parse_str(FILE, text);
     match r {
         Ok(_) => panic!(),
-        Err(e) => {
-            assert_eq!(e.line, 1);
-            assert_eq!(e.text, text);
+        Err(errors) => {
+            assert_eq!(errors[0].src.line, 1);
+            assert_eq!(errors[0].src.col, 1);
         }
     }
 }
+
+#[test]
+fn render_points_a_caret_at_the_bad_column() {
+    let text = "x = 1\ny = @\n";
+    let errors = parse_str(FILE, text).unwrap_err();
+    let rendered = errors[0].render(text);
+    let mut lines = rendered.lines();
+    assert_eq!(lines.next().unwrap(), "test:2:5: Unknown character");
+    assert_eq!(lines.next().unwrap(), "y = @");
+    assert_eq!(lines.next().unwrap(), "    ^");
+}
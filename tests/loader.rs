@@ -0,0 +1,41 @@
+use verbena::*;
+
+#[test]
+fn test_loader_insert_assigns_stable_ids() {
+    let mut loader = Loader::new();
+    let a = loader.insert("a.va", "print 1\n".to_string());
+    let b = loader.insert("b.va", "print 2\n".to_string());
+
+    assert_eq!(loader.path(a), "a.va");
+    assert_eq!(loader.path(b), "b.va");
+    assert_eq!(loader.text(a), "print 1\n");
+    assert_eq!(loader.text(b), "print 2\n");
+}
+
+#[test]
+fn test_loader_insert_same_path_reuses_id() {
+    let mut loader = Loader::new();
+    let first = loader.insert("a.va", "print 1\n".to_string());
+    let second = loader.insert("a.va", "print 2\n".to_string());
+
+    assert_eq!(first, second);
+    assert_eq!(loader.text(first), "print 2\n");
+}
+
+#[test]
+fn test_verror_format_error_points_at_caret() {
+    let mut loader = Loader::new();
+    let source = loader.insert("main.va", "let x = 1\nlet y = oops\n".to_string());
+
+    let caret = "let x = 1\nlet y = ".len();
+    let err = VError {
+        source,
+        caret,
+        msg: "Expected expression".to_string(),
+    };
+
+    let formatted = err.format_error(&loader);
+    assert!(formatted.contains("main.va:2:"));
+    assert!(formatted.contains("let y = oops"));
+    assert!(formatted.contains("Expected expression"));
+}
@@ -0,0 +1,117 @@
+use verbena::*;
+
+const FILE: &str = "test";
+
+#[test]
+fn if_else_closed_by_dedent_with_no_end() {
+    let text = "#offside\nif x\n  y = 1\nelse\n  y = 2\n";
+    let v = parse_str(FILE, &text).unwrap();
+    assert_eq!(v.len(), 1);
+    match &v[0] {
+        Stmt::If(_, _, yes, no) => {
+            assert_eq!(yes.len(), 1);
+            assert_eq!(no.len(), 1);
+        }
+        _ => panic!("Expected an if statement"),
+    }
+}
+
+#[test]
+fn nested_blocks_closed_by_dedent() {
+    let text = "#offside\nwhile x\n  if y\n    z = 1\n  z = 2\n";
+    let v = parse_str(FILE, &text).unwrap();
+    match &v[0] {
+        Stmt::While(_, _, body) => {
+            assert_eq!(body.len(), 2);
+            match &body[0] {
+                Stmt::If(_, _, yes, no) => {
+                    assert_eq!(yes.len(), 1);
+                    assert!(no.is_empty());
+                }
+                _ => panic!("Expected an if statement"),
+            }
+        }
+        _ => panic!("Expected a while statement"),
+    }
+}
+
+#[test]
+fn lambda_lookahead_backtrack_across_dedent() {
+    // `x` on its own line is a bare-identifier statement, so
+    // try_lambda_params' speculative lookahead lexes past it looking for
+    // `->`, crossing the dedent back to the `while` body's indent level
+    // before discovering there's no arrow and backtracking. That backtrack
+    // has to roll back `pending`/`indent_stack` too, or the dedent gets
+    // lost and the following line (a genuine lambda literal) never sees a
+    // correctly restored indentation level.
+    let text = "#offside\nwhile a\n  if b\n    x\n  y = z -> z + 1\n";
+    let v = parse_str(FILE, &text).unwrap();
+    assert_eq!(v.len(), 1);
+    match &v[0] {
+        Stmt::While(_, _, body) => {
+            assert_eq!(body.len(), 2);
+            assert!(matches!(&body[0], Stmt::If(..)));
+            match &body[1] {
+                Stmt::Expr(_, Expr::Assign(_, _, rhs)) => match rhs.as_ref() {
+                    Expr::Lambda(_, params, _) => {
+                        assert_eq!(params, &vec!["z".to_string()]);
+                    }
+                    _ => panic!("Expected a lambda expression"),
+                },
+                _ => panic!("Expected an assignment"),
+            }
+        }
+        _ => panic!("Expected a while statement"),
+    }
+}
+
+#[test]
+fn blank_and_comment_lines_do_not_affect_indentation() {
+    // The blank line and the comment-only line below are both unindented,
+    // but neither should be read as a dedent back to column 0: the offside
+    // transition only measures indentation up to the next *real* line, so
+    // the `if` body should still see `y = 1` and `y = 2` at the same depth
+    let text = "#offside\nif x\n  y = 1\n\n  # a comment on its own line\n  y = 2\n";
+    let v = parse_str(FILE, &text).unwrap();
+    assert_eq!(v.len(), 1);
+    match &v[0] {
+        Stmt::If(_, _, yes, no) => {
+            assert_eq!(yes.len(), 2);
+            assert!(no.is_empty());
+        }
+        _ => panic!("Expected an if statement"),
+    }
+}
+
+#[test]
+fn eof_flushes_all_outstanding_dedents() {
+    // No trailing newline and no explicit `end`/`else`: the file just stops
+    // while two levels of indentation are still open, so both blocks have
+    // to be closed by the end-of-file dedent flush, not left dangling
+    let text = "#offside\nwhile a\n  if b\n    x = 1";
+    let v = parse_str(FILE, &text).unwrap();
+    assert_eq!(v.len(), 1);
+    match &v[0] {
+        Stmt::While(_, _, body) => {
+            assert_eq!(body.len(), 1);
+            match &body[0] {
+                Stmt::If(_, _, yes, no) => {
+                    assert_eq!(yes.len(), 1);
+                    assert!(no.is_empty());
+                }
+                _ => panic!("Expected an if statement"),
+            }
+        }
+        _ => panic!("Expected a while statement"),
+    }
+}
+
+#[test]
+fn pragma_must_be_literal_first_line() {
+    // Without the `#offside` pragma on the very first line, indentation is
+    // not significant, so this same source needs an explicit `end` and
+    // fails to parse without one
+    let text = "if x\n  y = 1\nelse\n  y = 2\n";
+    let r = parse_str(FILE, &text);
+    assert!(r.is_err());
+}
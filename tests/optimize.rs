@@ -0,0 +1,45 @@
+use num_bigint::BigInt;
+use verbena::program::{Inst, Program};
+use verbena::Val;
+
+// Regression test for a bug where MakeClosure's `pc` operand -- a code
+// offset exactly like a branch target -- wasn't recognized by
+// branch_target/set_branch_target. That let Program::optimize do two
+// wrong things to a program containing a closure: apply_rewrites would
+// leave `pc` pointing at the closure's old location after earlier
+// instructions were folded away, and eliminate_dead_code would delete the
+// closure body outright if nothing but the MakeClosure itself referenced
+// it (e.g. a body placed right after an unconditional Br with no other
+// incoming jump).
+#[test]
+fn make_closure_survives_optimize() {
+    let code = vec![
+        Inst::Const(Val::Int(BigInt::from(1))),
+        Inst::Const(Val::Int(BigInt::from(2))),
+        Inst::Add, // folds with the two Consts above into a single Const
+        Inst::Pop,
+        Inst::Br(7), // jumps straight over the closure body below
+        Inst::LoadSlot(0), // closure body, reachable only via MakeClosure's pc
+        Inst::Return,
+        Inst::MakeClosure(5, 1, vec!["p".to_string()], Vec::new()),
+        Inst::Return,
+    ];
+    let program = Program::new(code).optimize(1);
+
+    let closure_pos = program
+        .code
+        .iter()
+        .position(|inst| matches!(inst, Inst::MakeClosure(..)))
+        .expect("MakeClosure should survive optimization");
+    let pc = match &program.code[closure_pos] {
+        Inst::MakeClosure(pc, _, _, _) => *pc,
+        _ => unreachable!(),
+    };
+    assert!(
+        matches!(program.code[pc], Inst::LoadSlot(0)),
+        "MakeClosure's pc should still point at its (possibly relocated) \
+         closure body, not be deleted as unreachable or left stale after \
+         earlier instructions were folded away; landed on {:?} instead",
+        program.code[pc]
+    );
+}
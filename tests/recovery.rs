@@ -0,0 +1,41 @@
+use std::fs;
+use verbena::*;
+
+// Returns the name bound by `x = ...`/`y = ...` style assignment statements,
+// skipping anything that isn't one (error placeholders, etc.), so the test
+// below can check which valid statements survived without caring exactly
+// how many diagnostics a bad line cascades into along the way
+fn assigned_names(v: &[Stmt]) -> Vec<&str> {
+    v.iter()
+        .filter_map(|stmt| match stmt {
+            Stmt::Expr(_, Expr::Assign(_, lhs, _)) => match lhs.as_ref() {
+                Expr::Atom(_, s) => Some(s.as_str()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn parse_recovering_collects_every_error_in_one_pass() {
+    // Two independent syntax errors on separate lines, with a valid
+    // statement sandwiched between them; a single-error parser would stop
+    // at the first `@` and never see the second
+    let text = "x = 1\n@\ny = 2\n@\nz = 3\n";
+    let path = "target/recovery-collects-every-error.va";
+    fs::write(path, text).unwrap();
+
+    let (v, errors) = parse_recovering(path);
+
+    // Both bad lines were reported, not just the first
+    assert!(errors.iter().any(|e| e.src.line == 2));
+    assert!(errors.iter().any(|e| e.src.line == 4));
+    assert!(errors.len() >= 2);
+
+    // The valid statements before, between, and after the bad lines still
+    // made it into the best-effort AST, in order
+    assert_eq!(assigned_names(&v), vec!["x", "y", "z"]);
+
+    let _ = fs::remove_file(path);
+}
@@ -163,6 +163,76 @@ fn test_lower() {
     assert_eq!(unicode.lower().to_string(), "こんにちはabc");
 }
 
+#[test]
+fn test_upper_multi_char_expansion() {
+    // German sharp s expands to "SS" under full uppercasing
+    let s = Str32::new("straße");
+    assert_eq!(s.upper().to_string(), "STRASSE");
+
+    // Ligature fi expands to "FI"
+    let lig = Str32::new("\u{fb01}le");
+    assert_eq!(lig.upper().to_string(), "FILE");
+}
+
+#[test]
+fn test_fold() {
+    let s1 = Str32::new("Straße");
+    let s2 = Str32::new("STRASSE");
+
+    // Folding lowercases and expands multi-char mappings
+    assert_eq!(s1.fold().to_string(), "strasse");
+    assert_ne!(s1.fold().to_string(), s2.fold().to_string());
+}
+
+#[test]
+fn test_eq_fold() {
+    let s1 = Str32::new("HELLO");
+    let s2 = Str32::new("hello");
+    let s3 = Str32::new("world");
+
+    assert!(s1.eq_fold(&s2));
+    assert!(!s1.eq_fold(&s3));
+
+    // Strings differing only by a multi-char case expansion should still fold equal
+    let ss = Str32::new("straße");
+    let ss_upper = Str32::new("STRASSE");
+    assert!(ss.eq_fold(&ss_upper));
+}
+
+#[test]
+fn test_grapheme_len() {
+    // A flag emoji is two chars but a single grapheme cluster
+    let flag = Str32::new("\u{1F1FA}\u{1F1F8}");
+    assert_eq!(flag.len(), 2);
+    assert_eq!(flag.grapheme_len(), 1);
+
+    let s = Str32::new("hello");
+    assert_eq!(s.grapheme_len(), 5);
+}
+
+#[test]
+fn test_graphemes() {
+    // "e" followed by a combining acute accent is one grapheme cluster
+    let s = Str32::new("e\u{0301}llo");
+    let gs = s.graphemes();
+    assert_eq!(gs.len(), 4);
+    assert_eq!(gs[0].to_string(), "e\u{0301}");
+    assert_eq!(gs[1].to_string(), "l");
+}
+
+#[test]
+fn test_grapheme_at() {
+    let s = Str32::new("e\u{0301}llo");
+    assert_eq!(s.grapheme_at(0).unwrap().to_string(), "e\u{0301}");
+    assert!(s.grapheme_at(10).is_err());
+}
+
+#[test]
+fn test_grapheme_substr() {
+    let s = Str32::new("e\u{0301}llo");
+    assert_eq!(s.grapheme_substr(0, 2).to_string(), "e\u{0301}l");
+}
+
 #[test]
 fn test_repeat() {
     let s = Str32::new("abc");
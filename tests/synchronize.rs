@@ -0,0 +1,46 @@
+use std::fs;
+use verbena::*;
+
+#[test]
+fn error_inside_an_if_body_does_not_swallow_the_closing_end() {
+    // A bad character inside an `if` body used to propagate past the
+    // enclosing `if`'s own unguarded newline check, collapsing the whole
+    // `if` (condition, body, and all) into a single error placeholder and
+    // leaving its `end` to be reported as an unmatched terminator instead
+    // of closing the block. It should instead be resynchronized locally,
+    // leaving the `if` statement intact and the `end` consumed normally.
+    let text = "if x\n@\nend\ny = 1\n";
+    let path = "target/synchronize-if-body-error.va";
+    fs::write(path, text).unwrap();
+
+    let (v, errors) = parse_recovering(path);
+
+    assert!(!errors.is_empty());
+    assert!(!errors.iter().any(|e| e.message.contains("Unmatched terminator")));
+
+    assert_eq!(v.len(), 2);
+    assert!(matches!(&v[0], Stmt::If(_, Expr::Atom(_, cond), ..) if cond == "x"));
+    // The statement after the `if` still parsed: the `end` wasn't consumed
+    // as part of resynchronizing past the error inside the body
+    assert!(matches!(&v[1], Stmt::Expr(_, Expr::Assign(..))));
+
+    let _ = fs::remove_file(path);
+}
+
+#[test]
+fn error_inside_a_while_body_does_not_swallow_the_closing_end() {
+    let text = "while x\n@\nend\ny = 1\n";
+    let path = "target/synchronize-while-body-error.va";
+    fs::write(path, text).unwrap();
+
+    let (v, errors) = parse_recovering(path);
+
+    assert!(!errors.is_empty());
+    assert!(!errors.iter().any(|e| e.message.contains("Unmatched terminator")));
+
+    assert_eq!(v.len(), 2);
+    assert!(matches!(&v[0], Stmt::While(_, Expr::Atom(_, cond), _) if cond == "x"));
+    assert!(matches!(&v[1], Stmt::Expr(_, Expr::Assign(..))));
+
+    let _ = fs::remove_file(path);
+}
@@ -0,0 +1,58 @@
+use verbena::*;
+
+const FILE: &str = "test";
+
+#[test]
+fn literal_only_template() {
+    let text = "print `hello world`";
+    let v = parse_str(FILE, &text).unwrap();
+    match &v[0] {
+        Stmt::Expr(_, Expr::Call(_, _, args)) => match &args[0] {
+            Expr::Template(_, pieces) => {
+                assert_eq!(pieces.len(), 1);
+                assert!(matches!(&pieces[0], Expr::Atom(_, s) if s == "\"hello world\""));
+            }
+            _ => panic!("Expected a template expression"),
+        },
+        _ => panic!("Expected a call expression"),
+    }
+}
+
+#[test]
+fn interpolated_template() {
+    let text = "print `hello ${name}!`";
+    let v = parse_str(FILE, &text).unwrap();
+    match &v[0] {
+        Stmt::Expr(_, Expr::Call(_, _, args)) => match &args[0] {
+            Expr::Template(_, pieces) => {
+                assert_eq!(pieces.len(), 3);
+                assert!(matches!(&pieces[0], Expr::Atom(_, s) if s == "\"hello \""));
+                assert!(matches!(&pieces[1], Expr::Atom(_, s) if s == "name"));
+                assert!(matches!(&pieces[2], Expr::Atom(_, s) if s == "\"!\""));
+            }
+            _ => panic!("Expected a template expression"),
+        },
+        _ => panic!("Expected a call expression"),
+    }
+}
+
+#[test]
+fn escaped_backtick_and_nested_braces() {
+    let text = "print `a\\`b ${ {1: 2} } c`";
+    let v = parse_str(FILE, &text).unwrap();
+    match &v[0] {
+        Stmt::Expr(_, Expr::Call(_, _, args)) => match &args[0] {
+            Expr::Template(_, pieces) => {
+                assert_eq!(pieces.len(), 3);
+                // The escaped backtick survives as a literal character
+                assert!(matches!(&pieces[0], Expr::Atom(_, s) if s == "\"a`b \""));
+                // The brace-depth tracker doesn't mistake the object literal's
+                // inner '}' for the interpolation's closing '}'
+                assert!(matches!(&pieces[1], Expr::Object(..)));
+                assert!(matches!(&pieces[2], Expr::Atom(_, s) if s == "\" c\""));
+            }
+            _ => panic!("Expected a template expression"),
+        },
+        _ => panic!("Expected a call expression"),
+    }
+}
@@ -0,0 +1,40 @@
+use verbena::*;
+
+const FILE: &str = "test";
+
+#[test]
+fn spans_are_ordered_and_well_formed() {
+    let text = "x = 1 + 2\n";
+    let toks = tokenize(FILE, text).unwrap();
+    assert_eq!(toks.last().unwrap().1, Tok::Eof);
+
+    let mut prev_end = 0;
+    for (start, _, end) in &toks {
+        assert!(*start >= prev_end);
+        assert!(end >= start);
+        prev_end = *end;
+    }
+}
+
+#[test]
+fn keyword_and_identifier_atoms_are_distinguished() {
+    let toks = tokenize(FILE, "if x\nend\n").unwrap();
+    let kinds: Vec<&Tok> = toks.iter().map(|(_, t, _)| t).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            &Tok::If,
+            &Tok::Atom("x".to_string()),
+            &Tok::Newline,
+            &Tok::End,
+            &Tok::Newline,
+            &Tok::Eof,
+        ]
+    );
+}
+
+#[test]
+fn stops_at_the_first_lex_error() {
+    let r = tokenize(FILE, "x = @\n");
+    assert!(r.is_err());
+}
@@ -296,3 +296,18 @@ fn test_debug_and_display() {
     assert_eq!(format!("{}", Val::False), "false");
     assert_eq!(format!("{}", Val::Null), "null");
 }
+
+#[test]
+fn test_inspect() {
+    // Unlike Display, inspect() quotes strings so they're distinguishable from bare words
+    assert_eq!(Val::Str("hi".to_string()).inspect(), "\"hi\"");
+    assert_eq!(Val::Num(1.5).inspect(), "1.5");
+    assert_eq!(Val::True.inspect(), "true");
+    assert_eq!(Val::Null.inspect(), "null");
+
+    let list = Val::List(std::rc::Rc::new(std::cell::RefCell::new(List::from(vec![
+        Val::Num(1.0),
+        Val::Str("a".to_string()),
+    ]))));
+    assert_eq!(list.inspect(), "[1, \"a\"]");
+}